@@ -0,0 +1,144 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use crate::{cleanup::CleanupMode, info::InfoFormat, tr};
+
+//Arguments starting with this prefix are reserved for Piton itself, and aren't passed to the app
+pub const PITON_ARG_PREFIX: &str = "--piton-";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ArgsError {
+    #[error("{}", tr!("args-error.unknown", arg = .0))]
+    UnknownSwitch(String),
+
+    #[error("{}", tr!("args-error.invalid-value", arg = .0, value = .1))]
+    InvalidValue(String, String)
+}
+
+#[derive(Debug, Default)]
+pub struct PitonArgs {
+    //--piton-repair: ignore existing runtimes and set up the runtime again
+    pub repair: bool,
+    //--piton-reinstall: like --piton-repair, but also remove all other existing runtimes
+    pub reinstall: bool,
+    //--piton-verbose: log debug messages to the console
+    pub verbose: bool,
+    //--piton-info[=text/json]: print a diagnostic report about the runtime setup instead of launching the app
+    pub info: Option<InfoFormat>,
+    //--piton-info-file=<path>: write the diagnostic report to the given file instead of the console
+    pub info_file: Option<PathBuf>,
+    //--piton-cleanup[=dry-run]: remove (or list) all runtimes and log files instead of launching the app
+    pub cleanup: Option<CleanupMode>,
+    //--piton-cleanup-shared: also clean up / reinstall runtimes shared with other apps outside the install directory (implies --piton-cleanup without --piton-reinstall)
+    pub cleanup_shared: bool,
+    //--piton-use-system-runtime=yes/no: override whether the system .NET runtime is tried first
+    pub use_system_runtime: Option<bool>,
+
+    //The remaining arguments, which are passed to the app
+    pub app_args: Vec<OsString>
+}
+
+impl PitonArgs {
+    //Splits Piton's switches off the given arguments (which shouldn't include the executable path)
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<PitonArgs, ArgsError> {
+        let mut piton_args = PitonArgs::default();
+        for arg in args {
+            //Arguments which aren't valid Unicode can't be Piton switches
+            let Some(switch) = arg.to_str().and_then(|a| a.strip_prefix(PITON_ARG_PREFIX)) else {
+                piton_args.app_args.push(arg);
+                continue;
+            };
+
+            let (name, value) = match switch.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (switch, None)
+            };
+            let flag = |flag: &mut bool| match value {
+                None => { *flag = true; Ok(()) }
+                Some(value) => Err(ArgsError::InvalidValue(format!("{PITON_ARG_PREFIX}{name}"), String::from(value)))
+            };
+
+            match name {
+                "repair" => flag(&mut piton_args.repair)?,
+                "reinstall" => flag(&mut piton_args.reinstall)?,
+                "verbose" => flag(&mut piton_args.verbose)?,
+                "info" => piton_args.info = Some(match value {
+                    None | Some("text") => InfoFormat::Text,
+                    Some("json") => InfoFormat::Json,
+                    Some(value) => return Err(ArgsError::InvalidValue(format!("{PITON_ARG_PREFIX}{name}"), String::from(value)))
+                }),
+                "info-file" => match value {
+                    Some(path) if !path.is_empty() => piton_args.info_file = Some(PathBuf::from(path)),
+                    value => return Err(ArgsError::InvalidValue(format!("{PITON_ARG_PREFIX}{name}"), String::from(value.unwrap_or_default())))
+                },
+                "cleanup" => piton_args.cleanup = Some(match value {
+                    None => CleanupMode::Remove,
+                    Some("dry-run") => CleanupMode::DryRun,
+                    Some(value) => return Err(ArgsError::InvalidValue(format!("{PITON_ARG_PREFIX}{name}"), String::from(value)))
+                }),
+                "cleanup-shared" => flag(&mut piton_args.cleanup_shared)?,
+                "use-system-runtime" => piton_args.use_system_runtime = Some(match value {
+                    Some("yes") => true,
+                    Some("no") => false,
+                    value => return Err(ArgsError::InvalidValue(format!("{PITON_ARG_PREFIX}{name}"), String::from(value.unwrap_or_default())))
+                }),
+                _ => return Err(ArgsError::UnknownSwitch(format!("{PITON_ARG_PREFIX}{name}")))
+            }
+        }
+
+        //Exporting the report implies requesting it
+        if piton_args.info_file.is_some() && piton_args.info.is_none() {
+            piton_args.info = Some(InfoFormat::Text);
+        }
+        if piton_args.cleanup_shared && piton_args.cleanup.is_none() && !piton_args.reinstall {
+            piton_args.cleanup = Some(CleanupMode::Remove);
+        }
+        Ok(piton_args)
+    }
+
+    //Whether existing runtimes should be ignored in favour of setting up a new one
+    pub const fn force_setup(&self) -> bool { self.repair || self.reinstall }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use crate::{cleanup::CleanupMode, info::InfoFormat};
+
+    use super::{ArgsError, PitonArgs};
+
+    fn parse(args: &[&str]) -> Result<PitonArgs, ArgsError> { PitonArgs::parse(args.iter().map(OsString::from)) }
+
+    #[test]
+    fn strips_piton_switches() {
+        let args = parse(&["foo", "--piton-verbose", "--bar", "--piton-use-system-runtime=no", "--piton-repair"]).unwrap();
+        assert!(args.verbose && args.repair && !args.reinstall && args.info.is_none());
+        assert_eq!(args.use_system_runtime, Some(false));
+        assert_eq!(args.app_args, ["foo", "--bar"]);
+    }
+
+    #[test]
+    fn parses_info_switches() {
+        assert_eq!(parse(&["--piton-info"]).unwrap().info, Some(InfoFormat::Text));
+        assert_eq!(parse(&["--piton-info=json"]).unwrap().info, Some(InfoFormat::Json));
+
+        let args = parse(&["--piton-info-file=report.txt"]).unwrap();
+        assert_eq!(args.info, Some(InfoFormat::Text));
+        assert_eq!(args.info_file.as_deref(), Some("report.txt".as_ref()));
+
+        assert_eq!(parse(&["--piton-cleanup"]).unwrap().cleanup, Some(CleanupMode::Remove));
+        assert_eq!(parse(&["--piton-cleanup=dry-run"]).unwrap().cleanup, Some(CleanupMode::DryRun));
+        assert_eq!(parse(&["--piton-cleanup-shared"]).unwrap().cleanup, Some(CleanupMode::Remove));
+        assert!(parse(&["--piton-cleanup=dry-run", "--piton-cleanup-shared"]).is_ok_and(|args| args.cleanup == Some(CleanupMode::DryRun) && args.cleanup_shared));
+        assert!(parse(&["--piton-reinstall", "--piton-cleanup-shared"]).is_ok_and(|args| args.cleanup.is_none() && args.cleanup_shared));
+    }
+
+    #[test]
+    fn rejects_invalid_switches() {
+        assert!(matches!(parse(&["--piton-foo"]), Err(ArgsError::UnknownSwitch(_))));
+        assert!(matches!(parse(&["--piton-info=xml"]), Err(ArgsError::InvalidValue(_, _))));
+        assert!(matches!(parse(&["--piton-cleanup=all"]), Err(ArgsError::InvalidValue(_, _))));
+        assert!(matches!(parse(&["--piton-use-system-runtime"]), Err(ArgsError::InvalidValue(_, _))));
+        assert!(matches!(parse(&["--piton-use-system-runtime=maybe"]), Err(ArgsError::InvalidValue(_, _))));
+    }
+}
@@ -1,9 +1,17 @@
-use crate::ui::UIDriver;
+use std::time::Duration;
 
-pub const IS_QUIET: bool = true;
+use crate::ui::{UIDriver, log::LogLevel};
+
+//The default verbosity of console / log file output - None disables it
+//The console verbosity can be overriden at runtime using the PITON_LOG environment variable
+pub const CONSOLE_LOG_LEVEL: Option<LogLevel> = None;
+pub const LOG_FILE_LEVEL: Option<LogLevel> = Some(LogLevel::Debug);
 
 pub const RUNTIME_DESCR_FILE: &str = "piton-runtime.yaml";
 
+//Translations are loaded from '<prefix>.<locale>.yaml' files next to the runtime descriptor
+pub const LOCALE_FILE_PREFIX: &str = "piton-locale";
+
 pub const RUNTIME_DIR_PATHS: &[&str] = &[
     "piton-runtime",
     "../piton-runtime"
@@ -11,6 +19,25 @@ pub const RUNTIME_DIR_PATHS: &[&str] = &[
 
 pub const USE_SYSTEM_RUNTIME: bool = true;
 
+//Launch configuration of the app
+//Environment variables are exported before the runtime is started, e.g. ("DOTNET_TieredPGO", "0")
+//Runtime properties are set through hostfxr, e.g. ("System.GC.Server", "true") - this is only possible for non-bundled apps running in-process
+pub const LAUNCH_ENV_VARS: &[(&str, &str)] = &[];
+pub const LAUNCH_RUNTIME_PROPERTIES: &[(&str, &str)] = &[];
+
+//Extra arguments which are passed to the app before / after the user's arguments
+pub const LAUNCH_ARGS_PREPEND: &[&str] = &[];
+pub const LAUNCH_ARGS_APPEND: &[&str] = &[];
+
+//Log files are written to the first writable directory, falling back to the user's state directory
+pub const USE_LOG_FILE: bool = true;
+pub const LOG_DIR_PATHS: &[&str] = &[
+    "piton-logs"
+];
+pub const LOG_FILE_NAME: &str = "piton.log";
+pub const LOG_FILE_MAX_SIZE: u64 = 1024 * 1024;
+pub const LOG_FILE_MAX_COUNT: usize = 3;
+
 #[allow(unreachable_code)]
 const fn det_default_ui_driver() -> UIDriver {
     #[cfg(feature = "ui-gui")] return UIDriver::Gui;
@@ -23,8 +50,35 @@ pub const UI_DRIVER: UIDriver = det_default_ui_driver();
 #[allow(unused)]
 pub const UI_APP_NAME: &str = "Everest Runtime Bootstrapper";
 
+//The ID of the app's .desktop file on Linux, which is used to attribute progress / notifications to it - defaults to the executable name
+#[allow(unused)]
+pub const UI_DESKTOP_ENTRY_ID: Option<&str> = None;
+
+//Desktop notifications are sent once a runtime setup which took at least this long finishes or fails
+pub const UI_NOTIFY_MIN_SETUP_TIME: Duration = Duration::from_secs(15);
+
 #[allow(unused)]
 pub const UI_ERRORMSG_HEADER: &str =
 r#"An error occurred while trying to prepare Everest for startup.
 Please report this in the Celeste discord server!
 (https://discord.gg/celeste, channel #modding_help)"#;
+
+//Error dialogs can offer to open the log folder, and link to a page where users can report the issue
+#[allow(unused)]
+pub const UI_ERROR_SHOW_LOG_FOLDER: bool = true;
+#[allow(unused)]
+pub const UI_ERROR_REPORT_URL: Option<&str> = Some("https://discord.gg/celeste");
+
+//Branding shown by the GUI dialogs - the icon / banner are PNG files next to the apphost, falling back to the embedded images (e.g. Some(include_bytes!("icon.png")))
+#[allow(unused)]
+pub const UI_ICON_FILE: &str = "piton-icon.png";
+#[allow(unused)]
+pub const UI_ICON_EMBEDDED: Option<&[u8]> = None;
+#[allow(unused)]
+pub const UI_BANNER_FILE: &str = "piton-banner.png";
+#[allow(unused)]
+pub const UI_BANNER_EMBEDDED: Option<&[u8]> = None;
+
+//The background colour of the area behind the banner, as RGB - defaults to the dialog's background
+#[allow(unused)]
+pub const UI_BANNER_COLOR: Option<(u8, u8, u8)> = None;
@@ -0,0 +1,237 @@
+use std::{error::Error, fmt::{Display, Formatter, self}, fs, io, path::{Component, Path, PathBuf}};
+
+use bytesize::ByteSize;
+
+use crate::{cfg, log, log_warn, runtime::is_runtime_dir, tr, ui};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupMode {
+    Remove,
+    DryRun
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupKind {
+    Runtime,
+    LogFile
+}
+
+//A file or directory created by Piton, which can be removed when uninstalling the app
+#[derive(Debug)]
+pub struct CleanupItem {
+    pub kind: CleanupKind,
+    pub path: PathBuf,
+    pub size: u64
+}
+
+impl CleanupItem {
+    fn new(kind: CleanupKind, path: PathBuf) -> io::Result<CleanupItem> {
+        let size = disk_usage(&path)?;
+        Ok(CleanupItem { kind, path, size })
+    }
+
+    fn remove(&self) -> io::Result<()> {
+        if self.path.is_dir() { fs::remove_dir_all(&self.path) } else { fs::remove_file(&self.path) }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CleanupError(Vec<(PathBuf, io::Error)>);
+
+impl Display for CleanupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&tr!("cleanup.failed"))?;
+        for (path, err) in &self.0 {
+            write!(f, "\n - {}: {err}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for CleanupError {}
+
+//Runtime directories outside the install directory might be shared with other apps
+fn is_shared_runtime_dir(dir: &Path) -> bool {
+    dir.is_absolute() || dir.components().any(|c| c == Component::ParentDir)
+}
+
+//Lists the runtime directories set up by Piton, skipping shared ones unless requested
+//Only directories marked as runtimes are included, so that unrelated directories which happen to have the same name are left alone
+//The exception is the primary runtime directory inside the install directory, which only the setup writes to, and which is unmarked if the setup was interrupted
+fn find_runtime_dirs(install_dir: &Path, include_shared: bool) -> impl Iterator<Item = PathBuf> + '_ {
+    cfg::RUNTIME_DIR_PATHS.iter().enumerate().filter(move |(idx, dir)| {
+        let runtime_dir = install_dir.join(dir);
+        if !is_runtime_dir(&runtime_dir) {
+            if *idx != 0 || is_shared_runtime_dir(Path::new(dir)) || !runtime_dir.is_dir() { return false; }
+
+            log!("Including partially set up runtime '{}'", runtime_dir.display());
+            return true;
+        }
+
+        let is_shared = is_shared_runtime_dir(Path::new(dir));
+        if is_shared && !include_shared {
+            log!("Skipping shared runtime '{}', pass --piton-cleanup-shared to remove it as well", runtime_dir.display());
+        }
+        !is_shared || include_shared
+    }).map(move |(_, dir)| install_dir.join(dir))
+}
+
+//Collects everything Piton created in the install directory and the log directories
+pub fn find_cleanup_items(install_dir: &Path, include_shared: bool) -> Result<Vec<CleanupItem>, CleanupError> {
+    let runtime_dirs = find_runtime_dirs(install_dir, include_shared).map(|dir| (CleanupKind::Runtime, dir));
+    let log_files = ui::log::find_log_files(install_dir).into_iter().map(|file| (CleanupKind::LogFile, file));
+
+    let mut items = Vec::new();
+    let mut errors = CleanupError::default();
+    for (kind, path) in runtime_dirs.chain(log_files) {
+        match CleanupItem::new(kind, path.clone()) {
+            Ok(item) => items.push(item),
+            Err(err) => errors.0.push((path, err))
+        }
+    }
+
+    if errors.0.is_empty() { Ok(items) } else { Err(errors) }
+}
+
+//Removes (or only lists, when doing a dry run) all files and directories created by Piton
+pub fn run_cleanup(install_dir: &Path, mode: CleanupMode, include_shared: bool) -> Result<(), CleanupError> {
+    let items = find_cleanup_items(install_dir, include_shared)?;
+    if items.is_empty() {
+        println!("{}", tr!("cleanup.nothing"));
+        return Ok(());
+    }
+
+    let mut errors = CleanupError::default();
+    for item in &items {
+        let size = ByteSize::b(item.size);
+        if mode == CleanupMode::DryRun {
+            println!("{}", tr!("cleanup.would-remove", path = item.path.display(), size = size));
+            continue;
+        }
+
+        log!("Removing '{}' ({size})", item.path.display());
+        match item.remove() {
+            Ok(()) => println!("{}", tr!("cleanup.removed", path = item.path.display(), size = size)),
+            Err(err) => {
+                log_warn!("Failed to remove '{}': {err}", item.path.display());
+                errors.0.push((item.path.clone(), err));
+            }
+        }
+    }
+    println!("{}", tr!("cleanup.total", size = ByteSize::b(items.iter().map(|item| item.size).sum())));
+
+    //Remove log directories which have been emptied, ignoring ones which still contain other files
+    if mode == CleanupMode::Remove {
+        for log_dir in items.iter().filter(|item| item.kind == CleanupKind::LogFile).filter_map(|item| item.path.parent()).filter(|dir| dir.is_dir()) {
+            let _ = fs::remove_dir(log_dir);
+        }
+    }
+
+    if errors.0.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+//Removes all existing runtimes before setting up a new one, as requested by --piton-reinstall
+pub fn remove_runtimes(install_dir: &Path, include_shared: bool) -> Result<(), CleanupError> {
+    let mut errors = CleanupError::default();
+    for runtime_dir in find_runtime_dirs(install_dir, include_shared) {
+        log!("Removing existing runtime '{}' for reinstall", runtime_dir.display());
+        if let Err(err) = fs::remove_dir_all(&runtime_dir) {
+            errors.0.push((runtime_dir, err));
+        }
+    }
+
+    if errors.0.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+//Determines the size of a file or directory tree, without following symlinks
+fn disk_usage(path: &Path) -> io::Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() { return Ok(metadata.len()); }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += disk_usage(&entry?.path())?;
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::args::PitonArgs;
+
+    use super::{find_cleanup_items, remove_runtimes, run_cleanup, CleanupMode};
+
+    #[test]
+    fn cleans_up_runtimes_and_logs() {
+        let base_dir = std::env::temp_dir().join(format!("piton-cleanup-test-{}", std::process::id()));
+        let install_dir = base_dir.join("app");
+        fs::create_dir_all(install_dir.join("piton-runtime/shared")).unwrap();
+        fs::write(install_dir.join("piton-runtime/shared/lib.so"), [0u8; 100]).unwrap();
+        fs::write(install_dir.join("piton-runtime/piton-runtime-id.txt"), "linux-x86_64 8.0.0").unwrap();
+        fs::create_dir_all(install_dir.join("piton-logs")).unwrap();
+        fs::write(install_dir.join("piton-logs/piton.log"), "log").unwrap();
+        fs::write(install_dir.join("piton-logs/piton.1.log"), "old log").unwrap();
+
+        //Directories without a runtime ID file aren't ours, even if they are in a runtime location
+        fs::create_dir_all(base_dir.join("piton-runtime")).unwrap();
+        fs::write(base_dir.join("piton-runtime/data.txt"), "data").unwrap();
+
+        let find_items = |include_shared| {
+            let mut items = find_cleanup_items(&install_dir, include_shared).unwrap().into_iter().map(|item| (item.path, item.size)).collect::<Vec<_>>();
+            items.sort();
+            items
+        };
+        assert_eq!(find_items(true), [
+            (install_dir.join("piton-logs/piton.1.log"), 7),
+            (install_dir.join("piton-logs/piton.log"), 3),
+            (install_dir.join("piton-runtime"), 118)
+        ]);
+
+        //Runtimes outside the install directory are only included if requested
+        fs::write(base_dir.join("piton-runtime/piton-runtime-id.txt"), "linux-x86_64 8.0.0").unwrap();
+        assert_eq!(find_items(false).len(), 3);
+        assert_eq!(find_items(true).first(), Some(&(install_dir.join("../piton-runtime"), 22)));
+
+        //Dry runs must not remove anything
+        run_cleanup(&install_dir, CleanupMode::DryRun, true).unwrap();
+        assert!(install_dir.join("piton-runtime").exists());
+
+        run_cleanup(&install_dir, CleanupMode::Remove, false).unwrap();
+        assert!(!install_dir.join("piton-runtime").exists() && !install_dir.join("piton-logs").exists());
+        assert!(base_dir.join("piton-runtime").exists());
+        assert!(find_cleanup_items(&install_dir, false).unwrap().is_empty());
+
+        //Partial setups at the primary location haven't been marked yet, but are still ours
+        fs::create_dir_all(install_dir.join("piton-runtime/shared")).unwrap();
+        fs::write(install_dir.join("piton-runtime/shared/lib.so"), [0u8; 50]).unwrap();
+        assert_eq!(find_items(false), [(install_dir.join("piton-runtime"), 50)]);
+        run_cleanup(&install_dir, CleanupMode::Remove, false).unwrap();
+        assert!(!install_dir.join("piton-runtime").exists());
+
+        fs::remove_dir_all(base_dir).unwrap();
+    }
+    #[test]
+    fn reinstall_keeps_shared_runtimes() {
+        let base_dir = std::env::temp_dir().join(format!("piton-reinstall-test-{}", std::process::id()));
+        let install_dir = base_dir.join("app");
+        for runtime_dir in [install_dir.join("piton-runtime"), base_dir.join("piton-runtime")] {
+            fs::create_dir_all(&runtime_dir).unwrap();
+            fs::write(runtime_dir.join("piton-runtime-id.txt"), "linux-x86_64 8.0.0").unwrap();
+        }
+
+        let args = PitonArgs::parse(["--piton-reinstall".into()]).unwrap();
+        remove_runtimes(&install_dir, args.cleanup_shared).unwrap();
+        assert!(!install_dir.join("piton-runtime").exists());
+        assert!(base_dir.join("piton-runtime").exists());
+
+        //Shared runtimes are only removed with an explicit opt-in
+        let args = PitonArgs::parse(["--piton-reinstall".into(), "--piton-cleanup-shared".into()]).unwrap();
+        assert!(args.cleanup.is_none());
+        remove_runtimes(&install_dir, args.cleanup_shared).unwrap();
+        assert!(!base_dir.join("piton-runtime").exists());
+
+        fs::remove_dir_all(base_dir).unwrap();
+    }
+}
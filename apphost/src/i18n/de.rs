@@ -0,0 +1,108 @@
+pub const MESSAGES: &[(&str, &str)] = &[
+    //Error messages shown by the apphost
+    ("error.dialog-title", "{app} - Fehler"),
+    ("error.app-binary-not-found", "Die verwaltete Anwendungsdatei '{path}' konnte nicht gefunden werden"),
+    ("error.runtime-descr", "Die Laufzeitbeschreibung für das Ziel '{target}' konnte nicht gelesen werden"),
+    ("error.remove-runtime", "Die bestehende Laufzeitumgebung konnte nicht entfernt werden"),
+    ("error.setup-runtime", "Die .NET-Laufzeitumgebung konnte nicht eingerichtet werden"),
+    ("error.launch-app", "Die Anwendungsdatei '{path}' konnte nicht gestartet werden"),
+    ("error.invalid-args", "Ungültige Kommandozeilenargumente"),
+    ("error.write-info", "Der Diagnosebericht konnte nicht nach '{path}' geschrieben werden"),
+    ("error.cleanup", "Die Laufzeitumgebungen und Logdateien konnten nicht entfernt werden"),
+    ("error.download-server-unreachable",
+r#"Die .NET-Laufzeitumgebung konnte nicht heruntergeladen werden.
+Der Downloadserver '{server}' ist nicht erreichbar.
+Bitte stelle sicher, dass du mit dem Internet verbunden bist, und versuche es erneut."#),
+
+    //Error report dialog
+    ("report.details", "Details"),
+    ("report.copy", "In die Zwischenablage kopieren"),
+    ("report.open-log-folder", "Logordner öffnen"),
+    ("report.report-issue", "Problem melden"),
+    ("report.close", "Schließen"),
+    ("report.retry", "Wiederholen"),
+    ("report.cancel", "Abbrechen"),
+    ("report.retry-prompt", "[{retry}]iederholen / [{quit}]eenden?"),
+    ("report.retry-key", "w"),
+    ("report.quit-key", "b"),
+    ("report.caused-by", "Verursacht durch"),
+    ("report.target", "Ziel"),
+    ("report.runtime-version", "Laufzeitversion"),
+    ("report.log-file", "Logdatei"),
+
+    //Runtime candidates
+    ("launch.rejected-candidates", "Keine Laufzeitumgebung konnte zum Starten der Anwendung verwendet werden:"),
+    ("launch.candidate-system", "System-.NET-Laufzeitumgebung"),
+    ("launch.candidate-existing", "bestehende Laufzeitumgebung '{path}'"),
+    ("launch.candidate-new", "neu eingerichtete Laufzeitumgebung '{path}'"),
+
+    //Diagnostic report
+    ("info.header", "{app} (Piton {version})"),
+    ("info.target", "Ziel: {target} (RID {rid}, native Architektur {arch})"),
+    ("info.runtime-descr", "Laufzeitumgebung: {target}, Version {version} ({format} von {url})"),
+    ("info.runtime-descr-error", "Laufzeitumgebung: {error}"),
+    ("info.system-runtime", "System-Laufzeitumgebung: hostfxr '{hostfxr}' in '{root}'"),
+    ("info.system-runtime-error", "System-Laufzeitumgebung: nicht gefunden ({error})"),
+    ("info.system-runtime-disabled", "System-Laufzeitumgebung: deaktiviert"),
+    ("info.disk-space", "Speicherplatz: {available} verfügbar in '{path}'"),
+    ("info.disk-space-error", "Speicherplatz: unbekannt ({error})"),
+    ("info.proxy", "Proxy-Einstellungen:"),
+    ("info.proxy-none", "Proxy-Einstellungen: keine"),
+
+    //Cleanup
+    ("cleanup.would-remove", "Würde '{path}' entfernen ({size})"),
+    ("cleanup.removed", "'{path}' entfernt ({size})"),
+    ("cleanup.total", "Insgesamt: {size}"),
+    ("cleanup.nothing", "Nichts zu entfernen"),
+    ("cleanup.failed", "Die folgenden Dateien konnten nicht entfernt werden:"),
+
+    //Command line argument errors
+    ("args-error.unknown", "Unbekannter Schalter '{arg}'"),
+    ("args-error.invalid-value", "Ungültiger Wert '{value}' für den Schalter '{arg}'"),
+
+    //Runtime errors
+    ("runtime-error.descr-parse", "Die Laufzeitbeschreibungsdatei '{file}' konnte nicht gelesen werden: {error}"),
+    ("runtime-error.unsupported-target", "Das aktuelle Laufzeitziel '{target}' wird nicht unterstützt"),
+    ("runtime-check.not-a-runtime", "kein Laufzeitverzeichnis"),
+    ("runtime-check.id-parse-error", "fehlerhafte Laufzeit-ID-Datei"),
+    ("runtime-check.wrong-target", "Laufzeit ist für das Ziel '{target}'"),
+    ("runtime-check.wrong-version", "Laufzeit hat die Version {version}"),
+    ("runtime-check.compatible", "kompatibel"),
+    ("runtime-error.hostfxr-messages", "Der .NET-Host hat folgende Fehler gemeldet:\n{messages}"),
+    ("runtime-error.unsupported-musl-target", "Das aktuelle Laufzeitziel '{target}' wird nicht unterstützt: Dieses System verwendet die musl-C-Bibliothek, aber es ist keine für musl erstellte .NET-Laufzeit verfügbar"),
+
+    //Setup errors
+    ("setup-error.download-server-unreachable", "Keine Verbindung zum Downloadserver '{server}' möglich: {error}"),
+    ("setup-error.async-runtime", "Die asynchrone Laufzeit konnte nicht initialisiert werden: {error}"),
+    ("setup-error.progress-action", "Der Fortschrittsdialog konnte nicht geöffnet werden: {error}"),
+    ("setup-error.download", "Die Laufzeitumgebung konnte nicht heruntergeladen werden: {error}"),
+    ("setup-error.hash-mismatch", "Falscher Hash der Laufzeitumgebung - der Download wurde möglicherweise manipuliert! (erwartet {expected}, erhalten {actual})"),
+    ("setup-error.decompress", "Die Laufzeitumgebung konnte nicht entpackt werden: {error}"),
+    ("setup-error.finalization", "Die Laufzeitumgebung konnte nicht fertiggestellt werden: {error}"),
+    ("setup-error.cancelled", "Der Einrichtungsdialog wurde abgebrochen"),
+    ("setup-error.file-traversal", "Versuchter Verzeichnisausbruch durch den Archivpfad '{path}' erkannt"),
+
+    //Progress dialog
+    ("setup.description", "Die .NET {version}-Laufzeitumgebung für {target} wird eingerichtet, bitte warten..."),
+    ("progress.connecting", "Verbindung zum Downloadserver wird hergestellt"),
+    ("progress.downloading", "Laufzeitumgebung wird heruntergeladen"),
+    ("progress.verifying", "Download wird überprüft"),
+    ("progress.extracting", "Archiv wird entpackt"),
+    ("progress.finalizing", "Wird abgeschlossen"),
+    ("progress.cancel", "Abbrechen"),
+    ("progress.cancel-confirm", "Bist du sicher? Der Download der Laufzeitumgebung wird abgebrochen."),
+    ("progress.cancelling", "Wird abgebrochen…"),
+    ("progress.count", "{stage}: {done}/{total}"),
+    ("progress.throughput", "({throughput})"),
+    ("progress.throughput-eta", "({throughput}, noch {eta})"),
+
+    //Desktop notifications
+    ("notify.setup-finished", "Die .NET-Laufzeitumgebung wurde eingerichtet"),
+    ("notify.setup-finished-body", "{app} wird gestartet..."),
+    ("notify.setup-failed", "Die .NET-Laufzeitumgebung konnte nicht eingerichtet werden"),
+
+    //Durations
+    ("duration.seconds", "{s}s"),
+    ("duration.minutes", "{m}m {s}s"),
+    ("duration.hours", "{h}h {m}m"),
+];
@@ -0,0 +1,108 @@
+pub const MESSAGES: &[(&str, &str)] = &[
+    //Error messages shown by the apphost
+    ("error.dialog-title", "{app} - Error"),
+    ("error.app-binary-not-found", "Failed to find managed application binary '{path}'"),
+    ("error.runtime-descr", "Failed to read the runtime descriptor for target '{target}'"),
+    ("error.remove-runtime", "Failed to remove existing runtime"),
+    ("error.setup-runtime", "Failed to set up the .NET runtime"),
+    ("error.launch-app", "Failed to launch the application binary '{path}'"),
+    ("error.invalid-args", "Invalid command line arguments"),
+    ("error.write-info", "Failed to write the diagnostic report to '{path}'"),
+    ("error.cleanup", "Failed to clean up the runtimes and log files"),
+    ("error.download-server-unreachable",
+r#"Failed to download the .NET runtime.
+The download server '{server}' could not be reached.
+Please ensure you are connected to the internet, then try again."#),
+
+    //Error report dialog
+    ("report.details", "Details"),
+    ("report.copy", "Copy to Clipboard"),
+    ("report.open-log-folder", "Open Log Folder"),
+    ("report.report-issue", "Report Issue"),
+    ("report.close", "Close"),
+    ("report.retry", "Retry"),
+    ("report.cancel", "Cancel"),
+    ("report.retry-prompt", "[{retry}]etry / [{quit}]uit?"),
+    ("report.retry-key", "r"),
+    ("report.quit-key", "q"),
+    ("report.caused-by", "Caused by"),
+    ("report.target", "Target"),
+    ("report.runtime-version", "Runtime version"),
+    ("report.log-file", "Log file"),
+
+    //Runtime candidates
+    ("launch.rejected-candidates", "No runtime could be used to launch the application:"),
+    ("launch.candidate-system", "system .NET runtime"),
+    ("launch.candidate-existing", "existing runtime '{path}'"),
+    ("launch.candidate-new", "newly set up runtime '{path}'"),
+
+    //Diagnostic report
+    ("info.header", "{app} (Piton {version})"),
+    ("info.target", "Target: {target} (RID {rid}, native architecture {arch})"),
+    ("info.runtime-descr", "Runtime: {target}, version {version} ({format} from {url})"),
+    ("info.runtime-descr-error", "Runtime: {error}"),
+    ("info.system-runtime", "System runtime: hostfxr '{hostfxr}' in '{root}'"),
+    ("info.system-runtime-error", "System runtime: not found ({error})"),
+    ("info.system-runtime-disabled", "System runtime: disabled"),
+    ("info.disk-space", "Disk space: {available} available in '{path}'"),
+    ("info.disk-space-error", "Disk space: unknown ({error})"),
+    ("info.proxy", "Proxy settings:"),
+    ("info.proxy-none", "Proxy settings: none"),
+
+    //Cleanup
+    ("cleanup.would-remove", "Would remove '{path}' ({size})"),
+    ("cleanup.removed", "Removed '{path}' ({size})"),
+    ("cleanup.total", "Total: {size}"),
+    ("cleanup.nothing", "Nothing to clean up"),
+    ("cleanup.failed", "The following files could not be removed:"),
+
+    //Command line argument errors
+    ("args-error.unknown", "Unknown switch '{arg}'"),
+    ("args-error.invalid-value", "Invalid value '{value}' for switch '{arg}'"),
+
+    //Runtime errors
+    ("runtime-error.descr-parse", "Failed to parse the '{file}' runtime descriptor file: {error}"),
+    ("runtime-error.unsupported-target", "Current runtime target '{target}' is not supported"),
+    ("runtime-check.not-a-runtime", "not a runtime directory"),
+    ("runtime-check.id-parse-error", "malformed runtime ID file"),
+    ("runtime-check.wrong-target", "runtime is for target '{target}'"),
+    ("runtime-check.wrong-version", "runtime has version {version}"),
+    ("runtime-check.compatible", "compatible"),
+    ("runtime-error.hostfxr-messages", "The .NET host reported the following errors:\n{messages}"),
+    ("runtime-error.unsupported-musl-target", "Current runtime target '{target}' is not supported: this system uses the musl C library, but no .NET runtime built for musl is available"),
+
+    //Setup errors
+    ("setup-error.download-server-unreachable", "Unable to connect to the runtime download server '{server}': {error}"),
+    ("setup-error.async-runtime", "Failed to initialize the async runtime: {error}"),
+    ("setup-error.progress-action", "Failed to open the progress dialog: {error}"),
+    ("setup-error.download", "Failed to download the runtime: {error}"),
+    ("setup-error.hash-mismatch", "Mismatching runtime hash - this might indicate that the download has been tampered with! (expected {expected}, got {actual})"),
+    ("setup-error.decompress", "Failed to decompress the runtime: {error}"),
+    ("setup-error.finalization", "Failed to finalize the runtime: {error}"),
+    ("setup-error.cancelled", "The user cancelled the setup dialog"),
+    ("setup-error.file-traversal", "Detected attempted file traversal through archive path '{path}'"),
+
+    //Progress dialog
+    ("setup.description", "Setting up the .NET {version} runtime for {target}, please wait..."),
+    ("progress.connecting", "Connecting to the download server"),
+    ("progress.downloading", "Downloading runtime"),
+    ("progress.verifying", "Verifying download"),
+    ("progress.extracting", "Unpacking archive"),
+    ("progress.finalizing", "Finalizing"),
+    ("progress.cancel", "Cancel"),
+    ("progress.cancel-confirm", "Are you sure? The runtime download will be aborted."),
+    ("progress.cancelling", "Cancelling…"),
+    ("progress.count", "{stage}: {done}/{total}"),
+    ("progress.throughput", "({throughput})"),
+    ("progress.throughput-eta", "({throughput}, {eta} remaining)"),
+
+    //Desktop notifications
+    ("notify.setup-finished", "The .NET runtime has been set up"),
+    ("notify.setup-finished-body", "{app} is starting..."),
+    ("notify.setup-failed", "Failed to set up the .NET runtime"),
+
+    //Durations
+    ("duration.seconds", "{s}s"),
+    ("duration.minutes", "{m}m {s}s"),
+    ("duration.hours", "{h}h {m}m"),
+];
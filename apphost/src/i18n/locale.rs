@@ -0,0 +1,88 @@
+//Detects the user's UI locale, returning it as a BCP 47 language tag (e.g. 'de-AT')
+pub fn detect_locale() -> Option<String> {
+    //Allow overriding the locale for testing purposes
+    if let Some(locale) = std::env::var("PITON_LOCALE").ok().and_then(|l| normalize_locale(&l)) {
+        return Some(locale);
+    }
+
+    os_locale().and_then(|l| normalize_locale(&l))
+}
+
+fn normalize_locale(locale: &str) -> Option<String> {
+    //Strip POSIX encoding / modifier suffixes (e.g. 'de_AT.UTF-8@euro')
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    if locale.is_empty() || locale == "C" || locale == "POSIX" { return None; }
+
+    //Normalize the language / region casing
+    let mut parts = locale.split(['_', '-']);
+    let mut tag = parts.next()?.to_ascii_lowercase();
+    for part in parts {
+        tag.push('-');
+        if part.len() == 2 {
+            tag.push_str(&part.to_ascii_uppercase());
+        } else {
+            tag.push_str(part);
+        }
+    }
+    Some(tag)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn os_locale() -> Option<String> {
+    //Follow the gettext lookup order
+    //LANGUAGE can contain a colon-separated list of locales, of which we only consider the first one
+    ["LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"].into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|val| String::from(val.split(':').next().unwrap_or_default()))
+        .find(|val| !val.is_empty())
+}
+
+#[cfg(target_os = "windows")]
+fn os_locale() -> Option<String> {
+    //The windows crate is only pulled in for the GUI driver, so declare the few functions we need ourselves
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetUserDefaultUILanguage() -> u16;
+        fn LCIDToLocaleName(locale: u32, name: *mut u16, name_len: i32, flags: u32) -> i32;
+    }
+
+    unsafe {
+        //LOCALE_NAME_MAX_LENGTH is 85 characters
+        let mut name_buf = [0_u16; 85];
+        let name_len = LCIDToLocaleName(GetUserDefaultUILanguage() as u32, name_buf.as_mut_ptr(), name_buf.len() as i32, 0);
+        if name_len <= 1 { return None; }
+
+        String::from_utf16(&name_buf[..(name_len - 1) as usize]).ok()
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn os_locale() -> Option<String> {
+    use std::ffi::{CStr, c_char, c_void};
+
+    //objc is only pulled in for the GUI driver, so go through CoreFoundation instead of NSLocale
+    type CFTypeRef = *const c_void;
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFLocaleCopyPreferredLanguages() -> CFTypeRef;
+        fn CFArrayGetCount(array: CFTypeRef) -> isize;
+        fn CFArrayGetValueAtIndex(array: CFTypeRef, idx: isize) -> CFTypeRef;
+        fn CFStringGetCString(string: CFTypeRef, buf: *mut c_char, buf_size: isize, encoding: u32) -> u8;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    unsafe {
+        //Query the first entry of CFLocaleCopyPreferredLanguages()
+        let langs = CFLocaleCopyPreferredLanguages();
+        if langs.is_null() { return None; }
+
+        let mut lang_buf = [0 as c_char; 64];
+        let has_lang = CFArrayGetCount(langs) > 0 && CFStringGetCString(CFArrayGetValueAtIndex(langs, 0), lang_buf.as_mut_ptr(), lang_buf.len() as isize, K_CF_STRING_ENCODING_UTF8) != 0;
+        CFRelease(langs);
+        if !has_lang { return None; }
+
+        CStr::from_ptr(lang_buf.as_ptr()).to_str().ok().map(String::from)
+    }
+}
@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fmt::{Display, Write}, fs, path::Path, sync::OnceLock};
+
+use crate::cfg;
+
+mod locale;
+mod en;
+mod de;
+
+//The built-in message catalogs - English must always come first, as it is used as the fallback for missing messages
+const BUILTIN_CATALOGS: &[(&str, &[(&str, &str)])] = &[
+    ("en", en::MESSAGES),
+    ("de", de::MESSAGES)
+];
+
+#[macro_export]
+macro_rules! tr {
+    ($key:literal) => { String::from($crate::i18n::message($key)) };
+    ($key:literal, $($arg:ident = $val:expr),+ $(,)?) => {
+        $crate::i18n::format_message($key, &[$((stringify!($arg), &$val as &dyn std::fmt::Display)),+])
+    };
+}
+
+struct Catalog {
+    locale: Option<String>,
+    messages: HashMap<String, String>
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+impl Catalog {
+    fn load(install_dir: Option<&Path>) -> Catalog {
+        //Determine the locale, and the candidate catalog names for it (from least to most specific)
+        let locale = locale::detect_locale();
+        let mut candidates: Vec<&str> = Vec::new();
+        if let Some(locale) = &locale {
+            if let Some((lang, _)) = locale.split_once('-') { candidates.push(lang); }
+            candidates.push(locale);
+        }
+
+        //Load the English catalog as the base, then layer the catalogs for the user's locale on top
+        let mut messages: HashMap<String, String> = BUILTIN_CATALOGS[0].1.iter().map(|(k, v)| (String::from(*k), String::from(*v))).collect();
+        for cand in candidates {
+            if let Some((_, builtin_msgs)) = BUILTIN_CATALOGS.iter().find(|(name, _)| name.eq_ignore_ascii_case(cand)) {
+                messages.extend(builtin_msgs.iter().map(|(k, v)| (String::from(*k), String::from(*v))));
+            }
+
+            //Allow the packaging app to ship additional translations next to the runtime descriptor
+            if let Some(install_dir) = install_dir {
+                let locale_file = install_dir.join(format!("{prefix}.{cand}.yaml", prefix = cfg::LOCALE_FILE_PREFIX));
+                if let Ok(locale_file) = fs::File::open(locale_file) {
+                    match serde_yaml::from_reader::<fs::File, HashMap<String, String>>(locale_file) {
+                        Ok(file_msgs) => messages.extend(file_msgs),
+                        Err(err) => crate::log_warn!("Failed to parse the translation file for locale '{cand}': {err}")
+                    }
+                }
+            }
+        }
+
+        Catalog { locale, messages }
+    }
+}
+
+//Initializes the message catalog, loading any translations shipped in the given install directory
+//Must be called before any messages are looked up, otherwise only the built-in catalogs are used
+pub fn init(install_dir: &Path) {
+    let catalog = CATALOG.get_or_init(|| Catalog::load(Some(install_dir)));
+    crate::log_debug!("Using locale '{}' for user-facing messages", catalog.locale.as_deref().unwrap_or("en"));
+}
+
+fn catalog() -> &'static Catalog { CATALOG.get_or_init(|| Catalog::load(None)) }
+
+//Looks up a message which might not be present in the catalog (e.g. because it overrides a setting from the packaging config)
+pub fn lookup(key: &str) -> Option<&'static str> { catalog().messages.get(key).map(String::as_str) }
+
+pub fn message(key: &str) -> &'static str {
+    lookup(key).unwrap_or_else(|| panic!("no message with key '{key}' in the message catalog"))
+}
+
+pub fn format_message(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    //Substitute all '{name}' placeholders with their argument
+    let mut msg = String::new();
+    let mut rem = message(key);
+    while let Some(start) = rem.find('{') {
+        msg.push_str(&rem[..start]);
+        rem = &rem[start..];
+
+        let Some(end) = rem.find('}') else { break; };
+        match args.iter().find(|(name, _)| *name == &rem[1..end]) {
+            Some((_, val)) => write!(msg, "{val}").unwrap(),
+            None => msg.push_str(&rem[..=end])
+        }
+        rem = &rem[end+1..];
+    }
+    msg.push_str(rem);
+    msg
+}
@@ -1,12 +1,20 @@
-use std::{process::ExitCode, fs, path::PathBuf, io};
+use std::{process::ExitCode, fs, path::PathBuf, io, time::Instant};
 
+mod args;
 mod cfg;
+mod cleanup;
+mod i18n;
+mod info;
 mod runtime;
 mod setup;
+mod target;
 mod ui;
 
+use args::PitonArgs;
+use info::InfoReport;
 use runtime::*;
 use setup::*;
+use target::TargetId;
 
 use crate::cfg::USE_SYSTEM_RUNTIME;
 
@@ -30,32 +38,38 @@ static APP_BUNDLE_MARKER: AppBundleMarker = AppBundleMarker {
 };
 
 macro_rules! handle_error {
-    ($res:expr, $($msg_arg:tt)+) => {
+    ($res:expr, $msg:expr) => {
         match $res {
             Ok(v) => v,
             Err(err) => {
-                let msg = format!($($msg_arg)+);
-                log!("Piton encountered an error while setting up the .NET runtime:");
-                log!("{}: {err:?}", msg);
-
-                let err_msg: String;
-                if cfg::UI_ERRORMSG_HEADER.len() > 0 {
-                    err_msg = format!("{header}\n\n{msg}:\n{err}", header = cfg::UI_ERRORMSG_HEADER);
-                } else {
-                    err_msg = format!("{msg}:\n{err}");
-                }
-                ui::show_error_msg(&err_msg);
-
+                let err: Box<dyn std::error::Error> = Box::from(err);
+                ui::show_error(&build_error_report($msg, err.as_ref()));
                 return ExitCode::FAILURE;
             }
         }
     };
 }
 
-macro_rules! run_app_binary {
-    ($runtime_dir:expr, $app_info:expr) => {
-        let app_res = handle_error!(launch_app_binary($runtime_dir, &$app_info), "Failed to launch the application binary '{}'", $app_info.app_path.display());
-        std::process::exit(app_res);
+fn build_error_report(msg: String, err: &dyn std::error::Error) -> ui::ErrorReport {
+    log_error!("Piton encountered an error while setting up the .NET runtime:");
+    log_error!("{}: {err:?}", msg);
+
+    //Translations may override the header from the packaging config
+    let header = i18n::lookup("error.header").unwrap_or(cfg::UI_ERRORMSG_HEADER);
+
+    let summary = if !header.is_empty() { format!("{header}\n\n{msg}") } else { msg };
+    ui::ErrorReport::new(summary, Some(err))
+}
+
+//Tries to launch the app through the given runtime candidate, exiting with the app's exit code if successful
+//Otherwise the failure is recorded, so that the next candidate can be tried
+macro_rules! try_run_app_binary {
+    ($rejected:expr, $candidate:expr, $runtime_dir:expr, $app_info:expr, $in_process:expr) => {
+        let launch_res = if $in_process { launch_app_binary(Some($runtime_dir), &$app_info) } else { launch_app_process($runtime_dir, &$app_info) };
+        match launch_res {
+            Ok(res) => std::process::exit(res),
+            Err(err) => $rejected.reject($candidate, err)
+        }
     }
 }
 
@@ -76,6 +90,16 @@ fn main() -> ExitCode {
         }
     }
 
+    //Apply the log verbosity override
+    let log_levels_res = ui::log::init_log_levels();
+
+    //Split off Piton's own switches from the app's arguments
+    //Errors are only reported once the UI is set up
+    let piton_args = PitonArgs::parse(std::env::args_os().skip(1));
+    if piton_args.as_ref().is_ok_and(|args| args.verbose) {
+        ui::log::set_log_level(Some(ui::log::LogLevel::Debug));
+    }
+
     //Setup paths
     let install_dir = if !cfg!(feature="testapp") {
         let mut exe_path = std::env::current_exe().unwrap();
@@ -90,70 +114,153 @@ fn main() -> ExitCode {
         path.push("test");
         path
     };
-    
-    let app_path = install_dir.join(&APP_BINARY_PATH[..APP_BINARY_PATH.chars().position(|c| c == '\x00').unwrap_or(APP_BINARY_PATH.len())]);
 
-    if !app_path.is_file() {
-        handle_error!(Err(io::Error::from(io::ErrorKind::NotFound)), "Failed to find managed application binary '{}'", app_path.display());
+    //Open the log file, unless it is about to be cleaned up
+    if !piton_args.as_ref().is_ok_and(|args| args.cleanup.is_some()) {
+        ui::log::open_log_file(&install_dir);
+    }
+    if let Err(err) = log_levels_res {
+        log_warn!("Ignoring invalid PITON_LOG value: {err}");
     }
 
-    let app_info = AppInfo { app_path: &app_path, bundle_offset: APP_BUNDLE_MARKER.bundle_offset };
+    //Load the message catalog for the user's locale
+    i18n::init(&install_dir);
+
+    #[cfg(feature = "ui-gui")]
+    ui::init_branding(&install_dir);
+
+    let piton_args = handle_error!(piton_args, tr!("error.invalid-args"));
+
+    //Remove everything we've set up instead of launching the app if requested
+    if let Some(mode) = piton_args.cleanup {
+        handle_error!(cleanup::run_cleanup(&install_dir, mode, piton_args.cleanup_shared), tr!("error.cleanup"));
+        return ExitCode::SUCCESS;
+    }
+    
+    let app_path = install_dir.join(&APP_BINARY_PATH[..APP_BINARY_PATH.chars().position(|c| c == '\x00').unwrap_or(APP_BINARY_PATH.len())]);
+    let app_info = AppInfo { app_path: &app_path, bundle_offset: APP_BUNDLE_MARKER.bundle_offset, args: &piton_args.app_args };
 
     //Read this target's runtime descriptor
-    let target_id = format!("{os}-{bits}", os = std::env::consts::OS, bits = std::env::consts::ARCH);
+    //Single-file bundles can only be run in-process, so they can't make use of native runtimes when we're being emulated
+    let target_id = TargetId::detect();
+    let native_arch = if !app_info.is_bundle() { target_id.detect_native_arch() } else { target_id.arch };
+    let process_arch = target_id.arch;
+    ui::set_report_context("report.target", target_id);
+
+    //Print a diagnostic report instead of launching the app if requested
+    if let Some(format) = piton_args.info {
+        let report = InfoReport::collect(&install_dir, &target_id, native_arch, piton_args.use_system_runtime.unwrap_or(USE_SYSTEM_RUNTIME)).render(format);
+        match &piton_args.info_file {
+            Some(info_file) => handle_error!(fs::write(info_file, report), tr!("error.write-info", path = info_file.display())),
+            None => println!("{report}")
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    if !app_path.is_file() {
+        handle_error!(Err(io::Error::from(io::ErrorKind::NotFound)), tr!("error.app-binary-not-found", path = app_path.display()));
+    }
 
     let runtimes_file = install_dir.join(cfg::RUNTIME_DESCR_FILE);
-    let runtime_descr = handle_error!(read_runtime_descr(&runtimes_file, &target_id), "Failed to read the runtime descriptor for target '{target_id}'");
+    let (target_id, runtime_descr) = handle_error!(read_runtime_descr(&runtimes_file, &target_id, native_arch), tr!("error.runtime-descr", target = target_id));
     log!("Read runtime descriptor for target '{target_id}': version {runtime_ver}", runtime_ver = runtime_descr.version);
 
+    //Runtimes of other architectures can't be loaded into our process
+    let in_process = target_id.arch == process_arch;
+    ui::set_report_context("report.runtime-version", &runtime_descr.version);
+
+    //Export the app's environment before any runtime is started
+    apply_launch_env();
+
+    //Try the runtime candidates in order: the system runtime, existing runtimes, and finally a freshly set up one
+    let mut rejected = RejectedCandidates::default();
+
+    //Existing runtimes (including the system one) are skipped when repairing / reinstalling
+    if piton_args.force_setup() {
+        log!("Forcing a new runtime setup as requested");
+    }
+    if piton_args.reinstall {
+        handle_error!(cleanup::remove_runtimes(&install_dir, piton_args.cleanup_shared), tr!("error.remove-runtime"));
+    }
+
     //Attempt to run through the system runtime
-    if USE_SYSTEM_RUNTIME {
+    if piton_args.use_system_runtime.unwrap_or(USE_SYSTEM_RUNTIME) && !piton_args.force_setup() {
         log!("Attempting to run through the system .NET runtime");
         match launch_app_binary(None, &app_info) {
             Ok(res) => std::process::exit(res),
-            Err(err) => log!("Failed to launch through the system .NET runtime: {err:?}")
+            Err(err) => rejected.reject(tr!("launch.candidate-system"), err)
         }
     }
 
     //Check if the runtime is already set up
     for runtime_dir in cfg::RUNTIME_DIR_PATHS { 
         let runtime_dir = install_dir.join(runtime_dir);
+        let candidate = tr!("launch.candidate-existing", path = runtime_dir.display());
         match check_runtime_install(&runtime_dir, &runtime_descr, &target_id) {
+            RuntimeCheckResult::NotARuntime => rejected.reject(candidate, RuntimeCheckResult::NotARuntime),
+            _ if piton_args.force_setup() => log!("Skipping existing runtime '{}'", runtime_dir.display()),
             RuntimeCheckResult::Compatible => {
                 log!("Detected compatible existing runtime '{}', launching...", runtime_dir.display());
-                run_app_binary!(Some(&runtime_dir), app_info);
+                try_run_app_binary!(rejected, candidate, &runtime_dir, app_info, in_process);
             }
-            check_res => log!("Existing runtime isn't compatible: {check_res:?}")
+            check_res => rejected.reject(candidate, check_res)
         };
     }
 
     log!("Unable to locate existing compatible runtime, setting up new one");
     let runtime_dir = install_dir.join(cfg::RUNTIME_DIR_PATHS[0]);
+    let new_candidate = tr!("launch.candidate-new", path = runtime_dir.display());
     
-    //Remove the old runtime
-    if runtime_dir.exists() {
-        handle_error!(fs::remove_dir_all(&runtime_dir), "Failed to remove existing runtime");
-    }
+    loop {
+        //Remove the old runtime - on Windows, this fails if an earlier candidate has already loaded it into our process
+        if runtime_dir.exists() {
+            if let Err(err) = fs::remove_dir_all(&runtime_dir) {
+                rejected.reject(new_candidate, format!("{}: {err}", tr!("error.remove-runtime")));
+                ui::show_error(&build_error_report(tr!("error.setup-runtime"), &rejected));
+                return ExitCode::FAILURE;
+            }
+        }
 
-    //Set up the runtime
-    let runtime_setup_res = setup_runtime(&target_id, &runtime_descr, &runtime_dir);
-    match runtime_setup_res {
-        Err(SetupError::DownloadServerUnreachable { server, error: err }) => {
-            ui::show_error_msg(&format!(
-r#"Failed to download the .NET runtime.
-The download server '{server}' could not be reached.
-Please ensure you are connected to the internet, then try again.
-
-Detailed error information:
-{err}"#
-            ));
+        //Set up the runtime
+        let setup_start = Instant::now();
+        let setup_res = setup_runtime(&target_id, &runtime_descr, &runtime_dir);
+
+        //Notify the user about the outcome of long setups, as they might have switched to other windows in the meantime
+        if setup_start.elapsed() >= cfg::UI_NOTIFY_MIN_SETUP_TIME {
+            match &setup_res {
+                Ok(()) => ui::notify(&tr!("notify.setup-finished"), &tr!("notify.setup-finished-body", app = cfg::UI_APP_NAME)),
+                Err(SetupError::Cancelled) => {}
+                Err(err) => ui::notify(&tr!("notify.setup-failed"), &err.to_string())
+            }
+        }
+
+        //Allow the user to retry if the download failed
+        let report = match setup_res {
+            Err(SetupError::DownloadServerUnreachable { server, error: err }) => {
+                log_error!("Failed to reach the download server: {err:?}");
+                ui::ErrorReport::new(tr!("error.download-server-unreachable", server = server), Some(err.as_ref()))
+            }
+            Err(err @ SetupError::DownloadError(_)) => build_error_report(tr!("error.setup-runtime"), &err),
+            Err(SetupError::Cancelled) => { return ExitCode::SUCCESS; }
+            Err(err) => {
+                rejected.reject(new_candidate, err);
+                ui::show_error(&build_error_report(tr!("error.setup-runtime"), &rejected));
+                return ExitCode::FAILURE;
+            }
+            Ok(()) => break
+        };
+
+        if !ui::show_retryable_error(&report) {
             return ExitCode::FAILURE;
         }
-        Err(SetupError::Cancelled) => { return ExitCode::SUCCESS; }
-        r => { handle_error!(r, "Failed to set up the .NET runtime"); }
+        log!("Retrying runtime setup...");
     }
 
     //Run the app binary now
     log!("Launching app after runtime setup completed successfully...");
-    run_app_binary!(Some(&runtime_dir), app_info);
+    try_run_app_binary!(rejected, new_candidate, &runtime_dir, app_info, in_process);
+
+    //All candidates failed
+    handle_error!(Err(rejected), tr!("error.launch-app", path = app_info.app_path.display()));
+    ExitCode::FAILURE
 }
\ No newline at end of file
@@ -1,7 +1,9 @@
-use std::{collections::HashMap, path::Path, error::Error, fs, io, env, ops::Deref};
+use std::{collections::HashMap, path::{Path, PathBuf}, error::Error, ffi::OsString, fmt::{Display, Formatter, self}, fs, io, env, ops::Deref, process, sync::Mutex, iter, mem, ptr};
 
-use serde::Deserialize;
-use netcorehost::{nethost, pdcstring::PdCString, hostfxr::Hostfxr, error::HostingError, bindings::char_t};
+use serde::{Deserialize, Serialize};
+use netcorehost::{nethost, pdcstring::PdCString, hostfxr::Hostfxr, error::HostingError, bindings::{char_t, hostfxr::{hostfxr_handle, hostfxr_initialize_parameters}}};
+
+use crate::{cfg, log, log_debug, log_warn, target::{ArchPreference, TargetArch, TargetId, TargetOS}, tr};
 
 #[derive(Deserialize, Debug, Clone, Copy)]
 pub struct Sha512Hash(#[serde(with="hex::serde")] pub [u8; 64]);
@@ -21,6 +23,19 @@ pub struct RuntimeDescriptor {
     pub download_format: RuntimeDownloadFormat
 }
 
+#[derive(Deserialize, Debug)]
+struct RuntimesFile {
+    //Which runtime to prefer when running under emulation (e.g. x86_64 binaries under Rosetta)
+    #[serde(rename="arch-preference", default="default_arch_preference")]
+    arch_preference: Vec<ArchPreference>,
+
+    #[serde(flatten)]
+    runtimes: HashMap<String, RuntimeDescriptor>
+}
+
+//Native runtimes have to be launched out of process, so they are opt-in
+fn default_arch_preference() -> Vec<ArchPreference> { vec![ArchPreference::Emulated] }
+
 #[derive(Deserialize, Debug, Clone)]
 pub enum RuntimeDownloadFormat {
     #[serde(rename="targz")] TarGz,
@@ -29,14 +44,19 @@ pub enum RuntimeDownloadFormat {
 
 #[derive(thiserror::Error, Debug)]
 pub enum RuntimeError {
-    #[error("Failed to parse the 'piton-runtime.yaml' runtime descriptor file: {0}")]
+    #[error("{}", tr!("runtime-error.descr-parse", file = cfg::RUNTIME_DESCR_FILE, error = .0))]
     RuntimeFileParse(Box<dyn Error>),
 
-    #[error("Current runtime target '{0}' is not supported")]
-    UnsupportedTarget(String)
+    #[error("{}", tr!("runtime-error.unsupported-target", target = .0))]
+    UnsupportedTarget(String),
+
+    #[error("{}", tr!("runtime-error.unsupported-musl-target", target = .0))]
+    UnsupportedMuslTarget(String)
 }
 
-pub fn read_runtime_descr(runtimes_file: &Path, target_id: &str) -> Result<RuntimeDescriptor, RuntimeError> {
+//Returns the runtime descriptor to use, together with the target it is for
+//This is a target of the native architecture if the descriptor prefers native runtimes over emulated ones
+pub fn read_runtime_descr(runtimes_file: &Path, target_id: &TargetId, native_arch: TargetArch) -> Result<(TargetId, RuntimeDescriptor), RuntimeError> {
     //Parse the runtimes file
     let runtimes = {
         let runtimes_file = match fs::File::open(runtimes_file) {
@@ -44,20 +64,34 @@ pub fn read_runtime_descr(runtimes_file: &Path, target_id: &str) -> Result<Runti
             Err(e) => return Err(RuntimeError::RuntimeFileParse(Box::new(e)))
         };
     
-        match serde_yaml::from_reader::<fs::File, HashMap<String, RuntimeDescriptor>>(runtimes_file) {
+        match serde_yaml::from_reader::<fs::File, RuntimesFile>(runtimes_file) {
             Ok(r) => r,
             Err(e) => return Err(RuntimeError::RuntimeFileParse(Box::new(e)))
         }
     };
 
-    //Get the runtime
-    match runtimes.get(target_id) {
-        Some(r) => Ok(r.clone()),
-        None => Err(RuntimeError::UnsupportedTarget(String::from(target_id)))
+    //Get the runtime of the first candidate target which has one, falling back to the targets' alias keys
+    for cand in target_id.candidates(native_arch, &runtimes.arch_preference) {
+        let Some(runtime) = cand.descr_keys().iter().find_map(|key| runtimes.runtimes.get(key)) else { continue; };
+
+        if native_arch != target_id.arch {
+            let kind = if cand.arch == native_arch { "native" } else { "emulated" };
+            log!("Running under emulation on a native {native} host, using the {kind} runtime for target '{cand}'", native = native_arch.descr_name());
+        }
+        return Ok((cand, runtime.clone()));
+    }
+
+    //Point out the libc mismatch explicitly, as the glibc runtime would seemingly be available
+    if target_id.os == TargetOS::LinuxMusl {
+        Err(RuntimeError::UnsupportedMuslTarget(target_id.to_string()))
+    } else {
+        Err(RuntimeError::UnsupportedTarget(target_id.to_string()))
     }
 }
 
-#[derive(Debug, Clone)]
+//Serialized with stable result names for machine-readable reports, while Display gives the localized description
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag="status", content="found", rename_all="kebab-case")]
 pub enum RuntimeCheckResult {
     NotARuntime,
     IDParseError,
@@ -66,9 +100,50 @@ pub enum RuntimeCheckResult {
     Compatible
 }
 
-pub fn check_runtime_install(runtime_dir: &Path, runtime_descr: &RuntimeDescriptor, target_id: &str) -> RuntimeCheckResult {
+impl Display for RuntimeCheckResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&match self {
+            RuntimeCheckResult::NotARuntime => tr!("runtime-check.not-a-runtime"),
+            RuntimeCheckResult::IDParseError => tr!("runtime-check.id-parse-error"),
+            RuntimeCheckResult::WrongTarget(target) => tr!("runtime-check.wrong-target", target = target),
+            RuntimeCheckResult::WrongVersion(version) => tr!("runtime-check.wrong-version", version = version),
+            RuntimeCheckResult::Compatible => tr!("runtime-check.compatible")
+        })
+    }
+}
+
+//The runtime candidates which couldn't be used to launch the app, together with the reason why
+#[derive(Debug, Default)]
+pub struct RejectedCandidates(Vec<(String, String)>);
+
+impl RejectedCandidates {
+    pub fn reject(&mut self, candidate: String, reason: impl Display) {
+        log_warn!("Rejected runtime candidate {candidate}: {reason}");
+        self.0.push((candidate, reason.to_string()));
+    }
+}
+
+impl Display for RejectedCandidates {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&tr!("launch.rejected-candidates"))?;
+        for (candidate, reason) in &self.0 {
+            write!(f, "\n - {candidate}: {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for RejectedCandidates {}
+
+//Runtime directories are marked by an ID file, which is written once the runtime has been set up
+const RUNTIME_ID_FILE: &str = "piton-runtime-id.txt";
+
+//Whether the directory contains a runtime set up by Piton, regardless of whether it's compatible with the app
+pub fn is_runtime_dir(runtime_dir: &Path) -> bool { runtime_dir.join(RUNTIME_ID_FILE).is_file() }
+
+pub fn check_runtime_install(runtime_dir: &Path, runtime_descr: &RuntimeDescriptor, target_id: &TargetId) -> RuntimeCheckResult {
     //Check if the runtime directory contains a piton-runtime-id.txt file with the wanted runtime ID
-    let dir_id_str = match fs::read_to_string(runtime_dir.join("piton-runtime-id.txt")) {
+    let dir_id_str = match fs::read_to_string(runtime_dir.join(RUNTIME_ID_FILE)) {
         Ok(id) => id,
         Err(_) => return RuntimeCheckResult::NotARuntime
     };
@@ -80,7 +155,7 @@ pub fn check_runtime_install(runtime_dir: &Path, runtime_descr: &RuntimeDescript
     if dir_id_split.next().is_some() { return RuntimeCheckResult::IDParseError; }
 
     //Check for compatibility
-    if dir_target_id != target_id {
+    if dir_target_id != target_id.descr_key() {
         RuntimeCheckResult::WrongTarget(String::from(dir_target_id))
     } else if dir_runtime_ver != runtime_descr.version {
         RuntimeCheckResult::WrongVersion(String::from(dir_runtime_ver))
@@ -89,19 +164,89 @@ pub fn check_runtime_install(runtime_dir: &Path, runtime_descr: &RuntimeDescript
     }
 }
 
-pub fn write_runtime_id(runtime_dir: &Path, target_id: &str, runtime_descr: &RuntimeDescriptor) -> io::Result<()> {
-    fs::write(runtime_dir.join("piton-runtime-id.txt"), format!("{target_id} {ver}", ver=runtime_descr.version))
+pub fn write_runtime_id(runtime_dir: &Path, target_id: &TargetId, runtime_descr: &RuntimeDescriptor) -> io::Result<()> {
+    fs::write(runtime_dir.join(RUNTIME_ID_FILE), format!("{target_id} {ver}", ver=runtime_descr.version))
+}
+
+//A hosting error, together with the error messages hostfxr printed while resolving the runtime / app
+#[derive(thiserror::Error, Debug)]
+#[error("{}", tr!("runtime-error.hostfxr-messages", messages = .messages.join("\n")))]
+pub struct HostfxrError {
+    pub messages: Vec<String>,
+    #[source] pub error: HostingError
+}
+
+//Messages printed by hostfxr during the current launch attempt
+static HOSTFXR_MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+extern "C" fn capture_hostfxr_message(msg: *const char_t) {
+    let msg = unsafe { hostfxr_str(msg) };
+    log_warn!("hostfxr: {msg}");
+
+    //Optionally pass the messages through, like the regular .NET apphost would
+    if env::var_os("PITON_HOSTFXR_STDERR").is_some_and(|v| !v.is_empty() && v != "0") {
+        eprintln!("{msg}");
+    }
+
+    HOSTFXR_MESSAGES.lock().unwrap().push(msg);
+}
+
+#[cfg(windows)]
+unsafe fn hostfxr_str(ptr: *const char_t) -> String {
+    let len = (0..).take_while(|&i| *ptr.add(i) != 0).count();
+    String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len))
+}
+
+#[cfg(not(windows))]
+unsafe fn hostfxr_str(ptr: *const char_t) -> String {
+    std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned()
 }
 
 pub struct AppInfo<'a> {
     pub app_path: &'a Path,
-    pub bundle_offset: i64
+    pub bundle_offset: i64,
+    pub args: &'a [OsString] //Without Piton's own switches
 }
 
 impl AppInfo<'_> {
     pub const fn is_bundle(&self) -> bool { self.bundle_offset > 0 }
 }
 
+//Exports the configured environment variables, so that they're inherited by the runtime / app
+//This has to happen before any runtime is started
+pub fn apply_launch_env() {
+    for (name, value) in cfg::LAUNCH_ENV_VARS {
+        log_debug!("Setting app environment variable {name}={value}");
+        env::set_var(name, value);
+    }
+}
+
+//The arguments passed to the app, i.e. the user's arguments surrounded by the configured extra arguments
+fn app_args(app_info: &AppInfo) -> Vec<OsString> {
+    let prepend = cfg::LAUNCH_ARGS_PREPEND.iter().map(OsString::from);
+    let append = cfg::LAUNCH_ARGS_APPEND.iter().map(OsString::from);
+    prepend.chain(app_info.args.iter().cloned()).chain(append).collect()
+}
+
+//Resolves the system hostfxr library, returning its path together with the root directory of the runtime it belongs to
+pub fn find_system_hostfxr() -> Result<(PathBuf, PathBuf), Box<dyn Error>> {
+    //This is rather jank since it assumes a particular layout of the runtime root
+    //However, hostfxr_resolver_t::dotnet_root() isn't exposed by the nethost library ._.
+    let hostfxr_path = nethost::get_hostfxr_path()?;
+    let hostfxr_path: &Path = hostfxr_path.as_ref();
+
+    let hostfxr_dir = hostfxr_path.parent().ok_or("hostfxr library path has no parent directory")?;
+
+    let fxr_dir = hostfxr_dir.parent().ok_or("hostfxr library directory has no parent directory")?;
+    if !fxr_dir.file_name().is_some_and(|n| n.eq("fxr")) { return Err("'fxr' directory is not named 'fxr'".into()); }
+
+    let host_dir = fxr_dir.parent().ok_or("'fxr' directory has no parent directory")?;
+    if !host_dir.file_name().is_some_and(|n| n.eq("host")) { return Err("'host' directory is not named 'host'".into()); }
+
+    let root_dir = host_dir.parent().ok_or("'host' directory has no parent directory")?;
+    Ok((PathBuf::from(hostfxr_path), PathBuf::from(root_dir)))
+}
+
 pub fn launch_app_binary(runtime_dir: Option<&Path>, app_info: &AppInfo) -> Result<i32, Box<dyn Error>> {
     //Load the hostfxr library
     let dotnet_root: PdCString;
@@ -114,61 +259,120 @@ pub fn launch_app_binary(runtime_dir: Option<&Path>, app_info: &AppInfo) -> Resu
         //Note that we do not support self-contained apps, so we don't have to pass the application root to check for those
         hostfxr = nethost::load_hostfxr()?;
 
-        //This is rather jank since it assumes a particular layout of the runtime root
-        //However, hostfxr_resolver_t::dotnet_root() isn't exposed by the nethost library ._.
-        let hostfxr_path = nethost::get_hostfxr_path()?;
-        let hostfxr_path: &Path = hostfxr_path.as_ref();
-
-        let hostfxr_dir = hostfxr_path.parent().ok_or("hostfxr library path has no parent directory")?;
-
-        let fxr_dir = hostfxr_dir.parent().ok_or("hostfxr library directory has no parent directory")?;
-        if !fxr_dir.file_name().map_or(false, |n| n.eq("fxr")) { return Err("'fxr' directory is not named 'fxr'".into()); }
-
-        let host_dir = fxr_dir.parent().ok_or("'fxr' directory has no parent directory")?;
-        if !host_dir.file_name().map_or(false, |n| n.eq("host")) { return Err("'host' directory is not named 'host'".into()); }
-
-        let root_dir = host_dir.parent().ok_or("'host' directory has no parent directory")?;
+        let (_, root_dir) = find_system_hostfxr()?;
         dotnet_root = PdCString::from_os_str(root_dir.as_os_str())?;
     }
 
     let hostfxr = hostfxr.lib.deref();
 
-    //Discard printed error messages as they only clutter up our own log message
-    //We handle errors based on the hostfxr return codes
+    //Capture printed error messages, so that they can be logged and included in error reports
+    //We still handle errors based on the hostfxr return codes
+    HOSTFXR_MESSAGES.lock().unwrap().clear();
     unsafe {
-        extern "C" fn nop_writer(_err: *const char_t) {}
-        hostfxr.hostfxr_set_error_writer(nop_writer);
+        hostfxr.hostfxr_set_error_writer(capture_hostfxr_message);
     }
 
-    //Run the app
+    //Prepare the command line
     let host_path = PdCString::from_os_str(env::current_exe()?.as_os_str())?;
-    let args: Vec<PdCString> = env::args_os().map(PdCString::from_os_str).collect::<Result<_, _>>()?;
+    let args: Vec<PdCString> = app_args(app_info).into_iter().map(PdCString::from_os_str).collect::<Result<_, _>>()?;
     let app_path = PdCString::from_os_str(app_info.app_path.as_os_str())?;
 
     //Apply required fixes
     #[cfg(unix)]
     unix::fixup_signal_handling();
 
-    let res = unsafe {
-        let args = args.iter().map(|s| s.as_ptr()).collect::<Vec<_>>();
+    //Single-file bundles can only be run through the hostfxr main routine, as the initialization API doesn't support them
+    //Note that there's no mechanism to distinguish app return codes from hostfxr ones in this case
+    //So if an app returns such an error code, we will think that we failed to execute the app
+    //Too bad!
+    if app_info.is_bundle() {
+        if !cfg::LAUNCH_RUNTIME_PROPERTIES.is_empty() {
+            log_warn!("Runtime properties can't be set for single-file bundles, ignoring them");
+        }
 
-        if app_info.is_bundle() {
+        let res = unsafe {
+            let args = iter::once(&host_path).chain(&args).map(|s| s.as_ptr()).collect::<Vec<_>>();
             hostfxr.hostfxr_main_bundle_startupinfo(args.len() as i32, args.as_ptr(), host_path.as_ptr(), dotnet_root.as_ptr(), app_path.as_ptr(), app_info.bundle_offset)
-        } else {
-            hostfxr.hostfxr_main_startupinfo(args.len() as i32, args.as_ptr(), host_path.as_ptr(), dotnet_root.as_ptr(), app_path.as_ptr())
+        }.ok_or("failed to invoke hostfxr main routine")?;
+
+        return match HostingError::known_from_status_code(res as u32) {
+            Ok(err) => Err(hostfxr_error(err)),
+            Err(_) => Ok(res)
+        };
+    }
+
+    //Initialize the host context
+    //The command line hostfxr expects starts with the app path instead of our own path
+    let init_params = hostfxr_initialize_parameters {
+        size: mem::size_of::<hostfxr_initialize_parameters>(),
+        host_path: host_path.as_ptr(),
+        dotnet_root: dotnet_root.as_ptr()
+    };
+    let mut host_ctx: hostfxr_handle = ptr::null();
+    let res = unsafe {
+        let args = iter::once(&app_path).chain(&args).map(|s| s.as_ptr()).collect::<Vec<_>>();
+        hostfxr.hostfxr_initialize_for_dotnet_command_line(args.len() as i32, args.as_ptr(), &init_params, &mut host_ctx)
+    }.ok_or("failed to invoke hostfxr initialization routine")?;
+
+    //Negative status codes indicate failures, while positive ones are still successes
+    if res < 0 {
+        if !host_ctx.is_null() {
+            unsafe { hostfxr.hostfxr_close(host_ctx); }
         }
-    }.ok_or("failed to invoke hostfxr main routine")?;
+        return Err(hostfxr_error(HostingError::from_status_code(res as u32)));
+    }
 
-    //Parse the result
-    //Note that there's no mechanism to distinguish app return codes from hostfxr ones
-    //So if an app returns such an error code, we will think that we failed to execute the app
-    //Too bad!
-    match HostingError::known_from_status_code(res as u32) {
-        Ok(err) => Err(Box::new(err)),
-        Err(_) => Ok(res)
+    //Set the configured runtime properties, as the runtime hasn't been started yet
+    let props_res = cfg::LAUNCH_RUNTIME_PROPERTIES.iter().try_for_each(|(name, value)| -> Result<(), Box<dyn Error>> {
+        log_debug!("Setting runtime property {name}={value}");
+
+        let (name, value) = (name.parse::<PdCString>()?, value.parse::<PdCString>()?);
+        let res = unsafe { hostfxr.hostfxr_set_runtime_property_value(host_ctx, name.as_ptr(), value.as_ptr()) }.ok_or("failed to invoke hostfxr runtime property routine")?;
+        if res < 0 {
+            return Err(hostfxr_error(HostingError::from_status_code(res as u32)));
+        }
+        Ok(())
+    });
+    if let Err(err) = props_res {
+        unsafe { hostfxr.hostfxr_close(host_ctx); }
+        return Err(err);
+    }
+
+    //Run the app
+    //Its exit code is passed through as-is, even if it happens to look like a hostfxr status code
+    let res = unsafe { hostfxr.hostfxr_run_app(host_ctx) };
+    unsafe { hostfxr.hostfxr_close(host_ctx); }
+    Ok(res.ok_or("failed to invoke hostfxr app run routine")?)
+}
+
+//Attaches the messages hostfxr printed to the given hosting error
+fn hostfxr_error(error: HostingError) -> Box<dyn Error> {
+    let messages = mem::take(&mut *HOSTFXR_MESSAGES.lock().unwrap());
+    if !messages.is_empty() {
+        Box::new(HostfxrError { messages, error })
+    } else {
+        Box::new(error)
     }
 }
 
+//Launches the app through the runtime's dotnet executable in a child process
+//This is required for runtimes of a different architecture than ours (e.g. native runtimes while we're being emulated), as they can't be loaded into our process
+pub fn launch_app_process(runtime_dir: &Path, app_info: &AppInfo) -> Result<i32, Box<dyn Error>> {
+    //The dotnet executable can't run single-file bundles
+    if app_info.is_bundle() { return Err("single-file app bundles can't be launched out-of-process".into()); }
+    if !cfg::LAUNCH_RUNTIME_PROPERTIES.is_empty() {
+        log_warn!("Runtime properties can't be set for apps launched out-of-process, ignoring them");
+    }
+
+    let dotnet_path = runtime_dir.join(if cfg!(windows) { "dotnet.exe" } else { "dotnet" });
+    let status = process::Command::new(dotnet_path)
+        .arg(app_info.app_path)
+        .args(app_args(app_info))
+        .status()?;
+
+    status.code().ok_or_else(|| format!("app process was terminated abnormally: {status}").into())
+}
+
 #[cfg(unix)]
 mod unix {
     use libc::{sigaction, sigaltstack, stack_t, SIGSEGV, SIG_DFL, SS_DISABLE};
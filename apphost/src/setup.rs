@@ -1,4 +1,4 @@
-use std::{error::Error, net::TcpStream, path::Path, io::{Cursor, self}, fs::{self}};
+use std::{error::Error, net::TcpStream, path::Path, io::{Cursor, self}, fs::{self}, time::{Instant, Duration}};
 
 use bytes::{BytesMut, BufMut, Bytes};
 use bytesize::ByteSize;
@@ -10,35 +10,35 @@ use thiserror::Error;
 use tokio::runtime::Runtime;
 use url::Url;
 
-use crate::{runtime::{RuntimeDescriptor, RuntimeDownloadFormat, write_runtime_id}, ui::{run_progress_action, ProgressAction}, log};
+use crate::{runtime::{RuntimeDescriptor, RuntimeDownloadFormat, write_runtime_id}, target::TargetId, ui::{run_progress_action, ProgressAction, ProgressStage, ProgressUpdate}, log, log_error, log_warn, log_debug, tr};
 
 type ErrorBox = Box<dyn Error>;
 type CrossThreadErrorBox = Box<dyn Error + Send + Sync>;
 
 #[derive(thiserror::Error, Debug)]
 pub enum SetupError {
-    #[error("Unable to connect to the runtime download server '{server}': {error}")]
+    #[error("{}", tr!("setup-error.download-server-unreachable", server = .server, error = .error))]
     DownloadServerUnreachable{ server: String, error: ErrorBox},
 
-    #[error("Failed to initialize the async runtime: {0}")]
+    #[error("{}", tr!("setup-error.async-runtime", error = .0))]
     AsyncRuntimeError(ErrorBox),
 
-    #[error("Failed to open the progress dialog: {0}")]
+    #[error("{}", tr!("setup-error.progress-action", error = .0))]
     ProgressActionError(ErrorBox),
 
-    #[error("Failed to download the runtime: {0}")]
+    #[error("{}", tr!("setup-error.download", error = .0))]
     DownloadError(CrossThreadErrorBox),
 
-    #[error("Mismatching runtime hash - this might indicate that the download has been tampered with! (expected {expected}, got {actual})")]
+    #[error("{}", tr!("setup-error.hash-mismatch", expected = .expected, actual = .actual))]
     DownloadHashMismatch{ expected: String, actual: String },
 
-    #[error("Failed to decompress the runtime: {0}")]
+    #[error("{}", tr!("setup-error.decompress", error = .0))]
     DecompressError(CrossThreadErrorBox),
 
-    #[error("Failed to finalize the runtime: {0}")]
+    #[error("{}", tr!("setup-error.finalization", error = .0))]
     FinalizationError(CrossThreadErrorBox),
 
-    #[error("The user cancelled the setup dialog")]
+    #[error("{}", tr!("setup-error.cancelled"))]
     Cancelled
 }
 
@@ -60,14 +60,14 @@ impl From<AsyncSetupError> for SetupError {
     }
 }
 
-pub fn setup_runtime(target_id: &str, runtime_descr: &RuntimeDescriptor, runtime_dir: &Path) -> Result<(), SetupError> {
+pub fn setup_runtime(target_id: &TargetId, runtime_descr: &RuntimeDescriptor, runtime_dir: &Path) -> Result<(), SetupError> {
     //Check that the download server is reachable
     let download_url = Url::parse(&runtime_descr.download_url).map_err(|e| SetupError::DownloadError(Box::new(e)))?;
     if let Some(download_host) = download_url.host() {
         if let Some(port) = download_url.port_or_known_default() {
             let download_host = download_host.to_string();
             if let Err(e) = TcpStream::connect((download_host.as_ref(), port)) {
-                log!("Failed to connect to the download server host: {download_host}");
+                log_error!("Failed to connect to the download server host: {download_host}");
                 return Err(SetupError::DownloadServerUnreachable { server: download_host, error: Box::new(e) });
             }
         }
@@ -77,7 +77,7 @@ pub fn setup_runtime(target_id: &str, runtime_descr: &RuntimeDescriptor, runtime
     let async_runtime = Runtime::new().map_err(|e| SetupError::AsyncRuntimeError(Box::new(e)))?;
 
     //Open the progress dialog
-    let diag_descr = format!("Setting up the .NET {} runtime, please wait...", runtime_descr.version);
+    let diag_descr = tr!("setup.description", version = runtime_descr.version, target = target_id);
     let Some(diag_res) = run_progress_action::<Result<(), AsyncSetupError>>(&diag_descr, move |act: &dyn ProgressAction| {
         //Download the runtime archive
         let runtime_data = download_runtime(act, &async_runtime, target_id, runtime_descr).map_err(AsyncSetupError::DownloadError)?;
@@ -85,16 +85,18 @@ pub fn setup_runtime(target_id: &str, runtime_descr: &RuntimeDescriptor, runtime
 
         //Validate the hash
         if let Some(download_hash) = runtime_descr.download_sha512 {
+            act.update_progress(&ProgressUpdate::indeterminate(ProgressStage::Verifying));
+
             let runtime_hash: &[u8] = &Sha512::digest(&runtime_data);
             if !download_hash.0.eq(runtime_hash) {
                 let expected_hash = hex::encode(download_hash.0);
                 let actual_hash = hex::encode(runtime_hash);
-                log!("Unexpected download hash: {} != {}", expected_hash, actual_hash);
+                log_error!("Unexpected download hash: {} != {}", expected_hash, actual_hash);
                 return Err(AsyncSetupError::DownloadHashMismatch(expected_hash, actual_hash));
             }
-            log!("Downloaded runtime hash matches expected hash");
+            log_debug!("Downloaded runtime hash matches expected hash");
         } else {
-            log!("Skipping has validation as no expected hash has been specified")
+            log_warn!("Skipping has validation as no expected hash has been specified")
         }
 
         //Decompress it
@@ -102,9 +104,16 @@ pub fn setup_runtime(target_id: &str, runtime_descr: &RuntimeDescriptor, runtime
             RuntimeDownloadFormat::TarGz => decompress_targz_runtime(act, runtime_dir, &runtime_data).map_err(AsyncSetupError::DecompressError)?,
             RuntimeDownloadFormat::Zip =>  decompress_zip_runtime(act, runtime_dir, &runtime_data).map_err(AsyncSetupError::DecompressError)?
         }
-        if act.is_cancelled() { return Ok(()); }
+        if act.is_cancelled() {
+            //Clean up the partially unpacked runtime before reporting back
+            log!("Removing partially unpacked runtime after cancellation");
+            if let Err(err) = fs::remove_dir_all(runtime_dir) {
+                log_warn!("Failed to remove partially unpacked runtime: {err}");
+            }
+            return Ok(());
+        }
 
-        act.set_progress("Finalizing", 1_f64);
+        act.update_progress(&ProgressUpdate::indeterminate(ProgressStage::Finalizing));
 
         //Write the runtime ID file
         write_runtime_id(runtime_dir, target_id, runtime_descr).map_err(|e| AsyncSetupError::FinalizationError(Box::new(e)))?;
@@ -112,7 +121,7 @@ pub fn setup_runtime(target_id: &str, runtime_descr: &RuntimeDescriptor, runtime
         log!("Successfully set up runtime version {ver} for target '{target_id}' in '{dir}'", ver=runtime_descr.version, dir=runtime_dir.display());
         Ok(())
     }).map_err(SetupError::ProgressActionError)? else {
-        println!("The user cancelled the operation");
+        log!("The user cancelled the operation");
         return Err(SetupError::Cancelled);
     };
 
@@ -121,9 +130,10 @@ pub fn setup_runtime(target_id: &str, runtime_descr: &RuntimeDescriptor, runtime
     Ok(())
 }
 
-fn download_runtime(act: &dyn ProgressAction, async_runtime: &Runtime, target_id: &str, runtime_descr: &RuntimeDescriptor) -> Result<Bytes, CrossThreadErrorBox> {
+fn download_runtime(act: &dyn ProgressAction, async_runtime: &Runtime, target_id: &TargetId, runtime_descr: &RuntimeDescriptor) -> Result<Bytes, CrossThreadErrorBox> {
     async_runtime.block_on(async move {
         //Create a new reqwest client and use it to fetch the runtime URL
+        act.update_progress(&ProgressUpdate::indeterminate(ProgressStage::Connecting));
         let client = Client::new();
         let resp = client.get(&runtime_descr.download_url).send().await?;
 
@@ -133,6 +143,7 @@ fn download_runtime(act: &dyn ProgressAction, async_runtime: &Runtime, target_id
         log!("Downloading runtime '{target_id}' from '{}' ({})...", runtime_descr.download_url, ByteSize::b(content_len));
 
         let mut data = BytesMut::new();
+        let start_time = Instant::now();
 
         //Handle chunks from the response stream
         let mut stream = resp.bytes_stream();
@@ -146,7 +157,17 @@ fn download_runtime(act: &dyn ProgressAction, async_runtime: &Runtime, target_id
             data.put_slice(&chunk);
 
             //Update the progress bar
-            act.set_progress(&format!("Downloading runtime '{target_id}': {}/{}", ByteSize::b(data.len() as u64), ByteSize::b(content_len)), (data.len() as f64) / (content_len as f64));
+            let num_downloaded = data.len() as u64;
+            let mut update = ProgressUpdate::new(ProgressStage::Downloading, (num_downloaded as f64) / (content_len as f64)).with_bytes(num_downloaded, content_len);
+
+            let elapsed = start_time.elapsed().as_secs_f64();
+            if elapsed > 0_f64 {
+                let throughput = (num_downloaded as f64) / elapsed;
+                let eta = (throughput > 0_f64).then(|| Duration::from_secs_f64((content_len.saturating_sub(num_downloaded) as f64) / throughput));
+                update = update.with_throughput(throughput as u64, eta);
+            }
+
+            act.update_progress(&update);
         }
         assert!(data.len() == content_len as usize);
 
@@ -156,21 +177,21 @@ fn download_runtime(act: &dyn ProgressAction, async_runtime: &Runtime, target_id
 
 #[derive(Error, Debug)]
 enum SecurityError {
-    #[error("Detected attempted file traversal through archive path '{0}'")]
+    #[error("{}", tr!("setup-error.file-traversal", path = .0))]
     AttemptedFileTraversal(String)
 }
 
 fn decompress_targz_runtime(act: &dyn ProgressAction, runtime_dir: &Path, data: &Bytes) -> Result<(), CrossThreadErrorBox> {
     fs::create_dir_all(runtime_dir)?;
     
-    //Initialize the progress bar
-    act.set_progress("Determining archive size", 0_f64);
+    //Show indeterminate progress while counting the entries
+    act.update_progress(&ProgressUpdate::indeterminate(ProgressStage::Extracting));
 
     let mut archive = tar::Archive::new(GzDecoder::new(Cursor::new(data)));
     let num_entries = archive.entries()?.count();
 
     log!("Unpacking TAR ({num_entries} entries)...");
-    act.set_progress(&format!("Unpacking archive: 0/{num_entries}"), 0_f64);
+    act.update_progress(&ProgressUpdate::new(ProgressStage::Extracting, 0_f64).with_entries(0, num_entries));
     
     //Unpack the TAR
     let mut archive = tar::Archive::new(GzDecoder::new(Cursor::new(data)));
@@ -188,7 +209,7 @@ fn decompress_targz_runtime(act: &dyn ProgressAction, runtime_dir: &Path, data:
         num_unpacked += 1;
 
         //Update the progress bar
-        act.set_progress(&format!("Unpacking archive: {num_unpacked}/{num_entries}"), (num_unpacked as f64) / (num_entries as f64));
+        act.update_progress(&ProgressUpdate::new(ProgressStage::Extracting, (num_unpacked as f64) / (num_entries as f64)).with_entries(num_unpacked, num_entries));
     }
 
     Ok(())
@@ -201,8 +222,8 @@ fn decompress_zip_runtime(dialog: &dyn ProgressAction, runtime_dir: &Path, data:
     let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
     let num_entries = archive.len();
 
-    println!("Unpacking ZIP ({num_entries} entries)...");
-    dialog.set_progress(&format!("Unpacking archive: 0/{num_entries}"), 0_f64);
+    log!("Unpacking ZIP ({num_entries} entries)...");
+    dialog.update_progress(&ProgressUpdate::new(ProgressStage::Extracting, 0_f64).with_entries(0, num_entries));
 
     for idx in 0..num_entries {
         let mut zip_file = archive.by_index(idx)?;
@@ -221,7 +242,7 @@ fn decompress_zip_runtime(dialog: &dyn ProgressAction, runtime_dir: &Path, data:
         io::copy(&mut zip_file, &mut fs::File::create(out_path)?)?;
 
         //Update the progress bar
-        dialog.set_progress(&format!("Unpacking archive: {}/{num_entries}", idx+1), (idx as f64) / (num_entries as f64));
+        dialog.update_progress(&ProgressUpdate::new(ProgressStage::Extracting, (idx as f64) / (num_entries as f64)).with_entries(idx+1, num_entries));
     }
 
     Ok(())
@@ -0,0 +1,435 @@
+use std::fmt::{Display, Formatter, self};
+
+use serde::Deserialize;
+
+use crate::log_debug;
+
+//There are no .NET runtimes for other targets, so fail early instead of picking the runtime of an unrelated target
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+compile_error!("Piton only supports Windows, Linux and macOS targets");
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64")))]
+compile_error!("Piton only supports x86, x86_64, arm and aarch64 targets");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetOS {
+    Windows,
+    Linux,
+    LinuxMusl,
+    MacOS
+}
+
+impl TargetOS {
+    //The name used in runtime descriptor keys
+    pub const fn descr_name(self) -> &'static str {
+        match self {
+            TargetOS::Windows => "windows",
+            TargetOS::Linux => "linux",
+            TargetOS::LinuxMusl => "linux-musl",
+            TargetOS::MacOS => "macos"
+        }
+    }
+
+    //The name used in .NET runtime identifiers
+    pub const fn rid_name(self) -> &'static str {
+        match self {
+            TargetOS::Windows => "win",
+            TargetOS::Linux => "linux",
+            TargetOS::LinuxMusl => "linux-musl",
+            TargetOS::MacOS => "osx"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetArch {
+    X86,
+    X64,
+    Arm,
+    Arm64
+}
+
+impl TargetArch {
+    //The name used in runtime descriptor keys (which matches the Rust architecture name)
+    pub const fn descr_name(self) -> &'static str {
+        match self {
+            TargetArch::X86 => "x86",
+            TargetArch::X64 => "x86_64",
+            TargetArch::Arm => "arm",
+            TargetArch::Arm64 => "aarch64"
+        }
+    }
+
+    //The name used in .NET runtime identifiers
+    pub const fn rid_name(self) -> &'static str {
+        match self {
+            TargetArch::X86 => "x86",
+            TargetArch::X64 => "x64",
+            TargetArch::Arm => "arm",
+            TargetArch::Arm64 => "arm64"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetId {
+    pub os: TargetOS,
+    pub arch: TargetArch
+}
+
+impl TargetId {
+    //The target of the system we're running on, which might differ from the one this binary was built for
+    //E.g. glibc binaries can run on musl systems through a compatibility layer, but the .NET runtime they'd pick can't
+    pub fn detect() -> TargetId {
+        //Other targets are rejected at compile time (see above), so the last branches only cover a single target each
+        let os = if cfg!(target_os = "windows") {
+            TargetOS::Windows
+        } else if cfg!(target_os = "macos") {
+            TargetOS::MacOS
+        } else if cfg!(target_env = "musl") || TargetId::is_musl_system() {
+            TargetOS::LinuxMusl
+        } else {
+            TargetOS::Linux
+        };
+
+        let arch = if cfg!(target_arch = "x86") {
+            TargetArch::X86
+        } else if cfg!(target_arch = "x86_64") {
+            TargetArch::X64
+        } else if cfg!(target_arch = "arm") {
+            TargetArch::Arm
+        } else {
+            TargetArch::Arm64
+        };
+
+        TargetId { os, arch }
+    }
+
+    //The architecture the host natively runs, which differs from the target's if we're being emulated (e.g. by Rosetta)
+    pub fn detect_native_arch(&self) -> TargetArch {
+        let hint = TargetId::native_arch_hint();
+        let native_arch = resolve_native_arch(self.arch, hint);
+        log_debug!("Native architecture hint: {hint:?} -> {}", native_arch.descr_name());
+        native_arch
+    }
+
+    //Returns the targets whose runtimes may be used, in the order given by the preference list
+    //Targets of other architectures only differ from this one in that they run natively instead of being emulated
+    pub fn candidates(&self, native_arch: TargetArch, preference: &[ArchPreference]) -> Vec<TargetId> {
+        let mut candidates = Vec::with_capacity(2);
+        for pref in preference {
+            let cand = match pref {
+                ArchPreference::Native => TargetId { os: self.os, arch: native_arch },
+                ArchPreference::Emulated => *self
+            };
+            if !candidates.contains(&cand) {
+                candidates.push(cand);
+            }
+        }
+
+        //Without emulation, there only is a single candidate
+        if native_arch == self.arch || candidates.is_empty() {
+            return vec![*self];
+        }
+        candidates
+    }
+
+    #[cfg(target_os = "windows")]
+    fn native_arch_hint() -> NativeArchHint { win::native_machine().map_or(NativeArchHint::None, NativeArchHint::WindowsNativeMachine) }
+
+    #[cfg(target_os = "macos")]
+    fn native_arch_hint() -> NativeArchHint { macos::is_translated().map_or(NativeArchHint::None, NativeArchHint::RosettaTranslated) }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    fn native_arch_hint() -> NativeArchHint { NativeArchHint::None }
+
+    #[cfg(target_os = "linux")]
+    fn is_musl_system() -> bool { linux::is_musl_system() }
+
+    #[cfg(not(target_os = "linux"))]
+    fn is_musl_system() -> bool { false }
+
+    //The target's key in the runtime descriptor, e.g. linux-musl-x86_64 or macos-aarch64
+    pub fn descr_key(&self) -> String { format!("{}-{}", self.os.descr_name(), self.arch.descr_name()) }
+
+    //The target's .NET runtime identifier, e.g. linux-musl-x64 or osx-arm64
+    pub fn rid(&self) -> String { format!("{}-{}", self.os.rid_name(), self.arch.rid_name()) }
+
+    //All keys the target's runtime descriptor entry may use, in order of preference
+    //Packagers can also key entries by RID, as that's what the .NET runtime downloads are named after
+    pub fn descr_keys(&self) -> Vec<String> {
+        let mut keys = vec![self.descr_key(), self.rid()];
+        keys.dedup();
+        keys
+    }
+}
+
+impl Display for TargetId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(&self.descr_key()) }
+}
+
+//Entries of the runtime descriptor's architecture preference list
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchPreference {
+    #[serde(rename="native")] Native,
+    #[serde(rename="emulated")] Emulated
+}
+
+//Platform-specific information about the host's native architecture, gathered by the detection code
+#[allow(unused)] //Each platform only gathers some kinds of hints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NativeArchHint {
+    None,
+    RosettaTranslated(bool), //Value of the macOS sysctl.proc_translated sysctl
+    WindowsNativeMachine(u16) //Native IMAGE_FILE_MACHINE_* value returned by IsWow64Process2
+}
+
+fn resolve_native_arch(process_arch: TargetArch, hint: NativeArchHint) -> TargetArch {
+    match hint {
+        NativeArchHint::None | NativeArchHint::RosettaTranslated(false) => process_arch,
+        //Rosetta only translates x86_64 code on Apple silicon
+        NativeArchHint::RosettaTranslated(true) => TargetArch::Arm64,
+        NativeArchHint::WindowsNativeMachine(machine) => match machine {
+            0x014c => TargetArch::X86, //IMAGE_FILE_MACHINE_I386
+            //WOW64 runs x86 code directly on x64 CPUs, so that's not emulation
+            0x8664 if process_arch == TargetArch::X86 => process_arch,
+            0x8664 => TargetArch::X64, //IMAGE_FILE_MACHINE_AMD64
+            0x01c4 => TargetArch::Arm, //IMAGE_FILE_MACHINE_ARMNT
+            0xaa64 => TargetArch::Arm64, //IMAGE_FILE_MACHINE_ARM64
+            _ => process_arch
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use std::{ffi::{c_char, c_void}, mem};
+
+    //Declared directly, as the windows crate is an optional dependency of the GUI driver
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetModuleHandleA(module_name: *const c_char) -> *mut c_void;
+        fn GetProcAddress(module: *mut c_void, proc_name: *const c_char) -> *const c_void;
+        fn GetCurrentProcess() -> *mut c_void;
+    }
+
+    //Returns the host's native machine type
+    //IsWow64Process2 is only available on Windows 10 and newer, so it has to be looked up dynamically
+    pub fn native_machine() -> Option<u16> {
+        type IsWow64Process2Fn = unsafe extern "system" fn(*mut c_void, *mut u16, *mut u16) -> i32;
+
+        unsafe {
+            let kernel32 = GetModuleHandleA(c"kernel32.dll".as_ptr());
+            if kernel32.is_null() { return None; }
+
+            let is_wow64_process2 = GetProcAddress(kernel32, c"IsWow64Process2".as_ptr());
+            if is_wow64_process2.is_null() { return None; }
+            let is_wow64_process2: IsWow64Process2Fn = mem::transmute(is_wow64_process2);
+
+            let (mut process_machine, mut native_machine) = (0_u16, 0_u16);
+            if is_wow64_process2(GetCurrentProcess(), &mut process_machine, &mut native_machine) == 0 { return None; }
+            Some(native_machine)
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::{mem, ptr};
+
+    //Checks if we're running under Rosetta
+    //The sysctl doesn't exist on systems without Rosetta, which implies we're running natively
+    pub fn is_translated() -> Option<bool> {
+        let mut translated: libc::c_int = 0;
+        let mut size = mem::size_of::<libc::c_int>();
+        let res = unsafe { libc::sysctlbyname(c"sysctl.proc_translated".as_ptr(), (&mut translated as *mut libc::c_int).cast(), &mut size, ptr::null_mut(), 0) };
+        if res != 0 {
+            return (std::io::Error::last_os_error().raw_os_error() == Some(libc::ENOENT)).then_some(false);
+        }
+        Some(translated == 1)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{fs, io::{self, Read, Seek, SeekFrom}, path::{Path, PathBuf}};
+
+    use crate::log_debug;
+
+    //Checks if the system's libc is musl
+    //The ELF interpreter of our own executable is the most reliable indicator, as it's the dynamic loader the system actually used to run us
+    pub fn is_musl_system() -> bool {
+        let own_interp = match elf_interpreter(Path::new("/proc/self/exe")) {
+            Ok(Some(interp)) => {
+                log_debug!("Own ELF interpreter: '{}'", interp.display());
+                Some(interp)
+            }
+            Ok(None) => { log_debug!("Own executable has no ELF interpreter"); None }
+            Err(err) => { log_debug!("Failed to determine own ELF interpreter: {err}"); None }
+        };
+        is_musl_system_in(Path::new("/"), own_interp.as_deref())
+    }
+
+    //Decides if the system below the given root directory is musl-based, given our own ELF interpreter (if any)
+    pub(super) fn is_musl_system_in(root: &Path, own_interp: Option<&Path>) -> bool {
+        //Glibc loaders provided by gcompat are symlinks to the musl loader, so resolve the interpreter before trusting it
+        if let Some(interp) = own_interp {
+            let interp = root.join(interp.strip_prefix("/").unwrap_or(interp));
+            return is_musl_loader(&interp) || fs::canonicalize(&interp).is_ok_and(|p| is_musl_loader(&p));
+        }
+
+        //Statically linked, so only treat the system as musl-based if there's no glibc to be found
+        //Debian-based distributions keep glibc in multiarch directories (e.g. /lib/x86_64-linux-gnu)
+        let has_musl_loader = fs::read_dir(root.join("lib")).is_ok_and(|mut entries| entries.any(|e| e.is_ok_and(|e| is_musl_loader(Path::new(&e.file_name())))));
+        let lib_dirs = ["lib", "lib64", "usr/lib", "usr/lib64"].map(|dir| root.join(dir));
+        let multiarch_dirs = lib_dirs.iter().filter_map(|dir| fs::read_dir(dir).ok()).flatten().filter_map(Result::ok)
+            .filter(|e| e.file_name().to_str().is_some_and(|n| n.contains("-linux-")))
+            .map(|e| e.path());
+        has_musl_loader && !lib_dirs.iter().cloned().chain(multiarch_dirs).any(|dir| dir.join("libc.so.6").exists())
+    }
+
+    fn is_musl_loader(path: &Path) -> bool {
+        path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("ld-musl-"))
+    }
+
+    //Returns the program interpreter (PT_INTERP) of the given ELF file
+    pub fn elf_interpreter(path: &Path) -> io::Result<Option<PathBuf>> {
+        const PT_INTERP: u32 = 3;
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut file = fs::File::open(path)?;
+        let mut header = [0_u8; 64];
+        file.read_exact(&mut header[..52])?;
+        if header[..4] != *b"\x7fELF" { return Err(invalid("not an ELF file")); }
+
+        let is_64 = match header[4] {
+            1 => false,
+            2 => { file.read_exact(&mut header[52..])?; true }
+            _ => return Err(invalid("unknown ELF class"))
+        };
+        let is_le = match header[5] {
+            1 => true,
+            2 => false,
+            _ => return Err(invalid("unknown ELF data encoding"))
+        };
+
+        let read_uint = |data: &[u8], off: usize, size: usize| -> u64 {
+            let bytes = &data[off..off+size];
+            if is_le {
+                bytes.iter().rev().fold(0, |v, b| (v << 8) | *b as u64)
+            } else {
+                bytes.iter().fold(0, |v, b| (v << 8) | *b as u64)
+            }
+        };
+
+        //Parse the program header table location, then look for the interpreter entry
+        let (ph_off, ph_ent_size, ph_num) = if is_64 {
+            (read_uint(&header, 0x20, 8), read_uint(&header, 0x36, 2), read_uint(&header, 0x38, 2))
+        } else {
+            (read_uint(&header, 0x1c, 4), read_uint(&header, 0x2a, 2), read_uint(&header, 0x2c, 2))
+        };
+        if ph_ent_size < if is_64 { 0x38 } else { 0x20 } { return Err(invalid("invalid program header size")); }
+
+        let mut ph = vec![0_u8; ph_ent_size as usize];
+        for i in 0..ph_num {
+            file.seek(SeekFrom::Start(ph_off + i * ph_ent_size))?;
+            file.read_exact(&mut ph)?;
+            if read_uint(&ph, 0, 4) != PT_INTERP as u64 { continue; }
+
+            let (off, size) = if is_64 {
+                (read_uint(&ph, 0x08, 8), read_uint(&ph, 0x20, 8))
+            } else {
+                (read_uint(&ph, 0x04, 4), read_uint(&ph, 0x10, 4))
+            };
+            if size > 4096 { return Err(invalid("ELF interpreter path is too long")); }
+
+            let mut interp = vec![0_u8; size as usize];
+            file.seek(SeekFrom::Start(off))?;
+            file.read_exact(&mut interp)?;
+
+            let interp = interp.split(|b| *b == 0).next().unwrap_or_default();
+            let interp = std::str::from_utf8(interp).map_err(|_| invalid("ELF interpreter path isn't valid UTF-8"))?;
+            return Ok(Some(PathBuf::from(interp)));
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve_native_arch, ArchPreference, NativeArchHint, TargetArch, TargetId, TargetOS};
+
+    #[test]
+    fn maps_target_names() {
+        let musl_x64 = TargetId { os: TargetOS::LinuxMusl, arch: TargetArch::X64 };
+        assert_eq!(musl_x64.descr_key(), "linux-musl-x86_64");
+        assert_eq!(musl_x64.rid(), "linux-musl-x64");
+
+        let macos_arm64 = TargetId { os: TargetOS::MacOS, arch: TargetArch::Arm64 };
+        assert_eq!(macos_arm64.descr_keys(), ["macos-aarch64", "osx-arm64"]);
+
+        //Descriptor keys and RIDs coincide for some targets
+        let linux_arm = TargetId { os: TargetOS::Linux, arch: TargetArch::Arm };
+        assert_eq!(linux_arm.descr_keys(), ["linux-arm"]);
+    }
+
+    #[test]
+    fn resolves_native_arch() {
+        assert_eq!(resolve_native_arch(TargetArch::X64, NativeArchHint::None), TargetArch::X64);
+        assert_eq!(resolve_native_arch(TargetArch::X64, NativeArchHint::RosettaTranslated(false)), TargetArch::X64);
+        assert_eq!(resolve_native_arch(TargetArch::X64, NativeArchHint::RosettaTranslated(true)), TargetArch::Arm64);
+        assert_eq!(resolve_native_arch(TargetArch::X64, NativeArchHint::WindowsNativeMachine(0xaa64)), TargetArch::Arm64);
+        assert_eq!(resolve_native_arch(TargetArch::X86, NativeArchHint::WindowsNativeMachine(0x8664)), TargetArch::X86);
+        assert_eq!(resolve_native_arch(TargetArch::X86, NativeArchHint::WindowsNativeMachine(0xaa64)), TargetArch::Arm64);
+        assert_eq!(resolve_native_arch(TargetArch::X86, NativeArchHint::WindowsNativeMachine(0)), TargetArch::X86);
+    }
+
+    #[test]
+    fn orders_candidates() {
+        let macos_x64 = TargetId { os: TargetOS::MacOS, arch: TargetArch::X64 };
+        let macos_arm64 = TargetId { os: TargetOS::MacOS, arch: TargetArch::Arm64 };
+        assert_eq!(macos_x64.candidates(TargetArch::Arm64, &[ArchPreference::Native, ArchPreference::Emulated]), [macos_arm64, macos_x64]);
+        assert_eq!(macos_x64.candidates(TargetArch::Arm64, &[ArchPreference::Emulated, ArchPreference::Native]), [macos_x64, macos_arm64]);
+        assert_eq!(macos_x64.candidates(TargetArch::Arm64, &[ArchPreference::Native]), [macos_arm64]);
+        assert_eq!(macos_x64.candidates(TargetArch::Arm64, &[]), [macos_x64]);
+
+        //Without emulation the preference doesn't matter
+        assert_eq!(macos_x64.candidates(TargetArch::X64, &[ArchPreference::Native, ArchPreference::Emulated]), [macos_x64]);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn detects_own_libc() {
+        let interp = super::linux::elf_interpreter(std::path::Path::new("/proc/self/exe")).expect("failed to parse own executable").expect("own executable has no interpreter");
+        let is_musl = interp.file_name().unwrap().to_str().unwrap().starts_with("ld-musl-");
+        assert_eq!(is_musl, cfg!(target_env = "musl"));
+        assert_eq!(TargetId::detect().os == TargetOS::LinuxMusl, is_musl);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn detects_musl_system() {
+        use std::{fs, os::unix::fs::symlink, path::Path};
+
+        use super::linux::is_musl_system_in;
+
+        let root = std::env::temp_dir().join(format!("piton-musl-test-{}", std::process::id()));
+        fs::create_dir_all(root.join("lib")).unwrap();
+        fs::write(root.join("lib/ld-musl-x86_64.so.1"), "").unwrap();
+        symlink("ld-musl-x86_64.so.1", root.join("lib/ld-linux-x86-64.so.2")).unwrap();
+
+        //Our own interpreter decides, even if it's a glibc loader name provided by gcompat
+        assert!(is_musl_system_in(&root, Some(Path::new("/lib/ld-musl-x86_64.so.1"))));
+        assert!(is_musl_system_in(&root, Some(Path::new("/lib/ld-linux-x86-64.so.2"))));
+        assert!(!is_musl_system_in(&root, Some(Path::new("/lib64/ld-linux-x86-64.so.2"))));
+
+        //Without an interpreter, glibc in a multiarch directory takes precedence over the musl loader
+        assert!(is_musl_system_in(&root, None));
+        fs::create_dir_all(root.join("usr/lib/x86_64-linux-gnu")).unwrap();
+        fs::write(root.join("usr/lib/x86_64-linux-gnu/libc.so.6"), "").unwrap();
+        assert!(!is_musl_system_in(&root, None));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
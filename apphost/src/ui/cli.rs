@@ -1,37 +1,151 @@
-use std::error::Error;
+use std::{error::Error, cell::Cell, io::{self, IsTerminal, Write}, time::Duration};
 
 use indicatif::{ProgressBar, ProgressStyle};
 
-use super::{ProgressAction, log::LogHook};
+use crate::{log_warn, tr};
 
-pub type CLIProgressAction = ProgressBar;
+use super::{ErrorReport, ProgressAction, ProgressUpdate, log::{LogHook, LogSinkKind, fmt_console_msg}};
+
+//The built-in English template for byte progress, used if a translated one can't be parsed
+const FALLBACK_BYTES_TEMPLATE: &str = "{prefix}\n> {msg}: {bytes}/{total_bytes} ({bytes_per_sec}, {eta} remaining)\n{wide_bar}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BarStyle {
+    Fraction,
+    Bytes,
+    Indeterminate
+}
+
+pub struct CLIProgressAction {
+    bar: ProgressBar,
+    style: Cell<BarStyle>
+}
+
+impl CLIProgressAction {
+    fn set_style(&self, style: BarStyle) {
+        let prev_style = self.style.replace(style);
+        if prev_style == style { return; }
+
+        //Switch between the fractional progress bar, one which lets indicatif render byte counts, throughput and ETA, and a spinner
+        match style {
+            BarStyle::Fraction => {
+                self.bar.set_style(ProgressStyle::default_bar().template("{prefix}\n> {msg}\n{wide_bar}").expect("failed to create progress bar style"));
+                self.bar.set_length(100_000);
+            }
+            BarStyle::Bytes => {
+                let progress_txt = tr!("progress.count", stage = "{msg}", done = "{bytes}", total = "{total_bytes}");
+                let throughput_txt = tr!("progress.throughput-eta", throughput = "{bytes_per_sec}", eta = "{eta}");
+                self.bar.set_style(bytes_style(&format!("{{prefix}}\n> {progress_txt} {throughput_txt}\n{{wide_bar}}")));
+            }
+            BarStyle::Indeterminate => {
+                self.bar.set_style(ProgressStyle::default_spinner().template("{prefix}\n> {msg} {spinner}").expect("failed to create progress bar style"));
+            }
+        }
+
+        //The spinner has to be animated even when there are no updates
+        if style == BarStyle::Indeterminate {
+            self.bar.enable_steady_tick(Duration::from_millis(100));
+        } else if prev_style == BarStyle::Indeterminate {
+            self.bar.disable_steady_tick();
+        }
+    }
+}
 
 impl ProgressAction for CLIProgressAction {
+    fn update_progress(&self, update: &ProgressUpdate) {
+        if let (Some(done), Some(total)) = (update.bytes_done, update.bytes_total) {
+            self.set_style(BarStyle::Bytes);
+            self.bar.set_message(update.stage.to_string());
+            self.bar.set_length(total);
+            self.bar.set_position(done);
+        } else if update.indeterminate {
+            self.set_indeterminate_progress(&update.to_string());
+        } else {
+            self.set_progress(&update.to_string(), update.fract);
+        }
+    }
+
     fn set_progress(&self, txt: &str, fract: f64) {
-        self.set_message(String::from(txt));
-        self.set_position((fract * 100_000_f64) as u64);
+        self.set_style(BarStyle::Fraction);
+        self.bar.set_message(String::from(txt));
+        self.bar.set_position((fract * 100_000_f64) as u64);
     }
- 
+
+    fn set_indeterminate_progress(&self, txt: &str) {
+        self.set_style(BarStyle::Indeterminate);
+        self.bar.set_message(String::from(txt));
+    }
+
     fn is_cancelled(&self) -> bool { false }
 }
 
+//The template is built from translations which are loaded at runtime, so a broken one must not crash the setup
+fn bytes_style(template: &str) -> ProgressStyle {
+    ProgressStyle::default_bar().template(template).unwrap_or_else(|err| {
+        log_warn!("Invalid progress bar template '{template}', falling back to the built-in one: {err}");
+        ProgressStyle::default_bar().template(FALLBACK_BYTES_TEMPLATE).expect("failed to create progress bar style")
+    })
+}
+
 pub fn run_progress_action<T: Send>(descr: &str, action: impl FnOnce(&CLIProgressAction) -> T + Send) -> Result<Option<T>, Box<dyn Error>> {
     //Create the progress bar
     let prog_bar = ProgressBar::new(100_000)
         .with_style(ProgressStyle::default_bar().template("{prefix}\n> {msg}\n{wide_bar}").expect("failed to create progress bar style"))
         .with_prefix(String::from(descr));
-    let prog_bar = &prog_bar;
+    let prog_act = CLIProgressAction { bar: prog_bar.clone(), style: Cell::new(BarStyle::Fraction) };
 
     //Hook the logs to properly work with the progress bar
-    let log_hook_fnc = |msg: &_| prog_bar.println(msg);
-    let log_hook = LogHook::create(&log_hook_fnc);
+    let log_hook = {
+        let prog_bar = prog_bar.clone();
+        LogHook::register(LogSinkKind::Console, move |level, msg| prog_bar.println(fmt_console_msg(level, msg)))
+    };
 
     //Run the action
-    let res = action(prog_bar);
+    let res = action(&prog_act);
 
     //Cleanup
     prog_bar.finish_and_clear();
     drop(log_hook);
 
     Ok(Some(res))
-}
\ No newline at end of file
+}
+
+pub fn prompt_retry(report: &ErrorReport) -> bool {
+    eprintln!("{report}");
+
+    //Only prompt when there's a user to answer
+    if !io::stdin().is_terminal() { return false; }
+
+    let (retry_key, quit_key) = (tr!("report.retry-key"), tr!("report.quit-key"));
+    loop {
+        eprint!("\n{} ", tr!("report.retry-prompt", retry = retry_key, quit = quit_key));
+        let _ = io::stderr().flush();
+
+        let mut answer = String::new();
+        match io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {}
+        }
+
+        let answer = answer.trim();
+        if answer.eq_ignore_ascii_case(&retry_key) { return true; }
+        if answer.eq_ignore_ascii_case(&quit_key) { return false; }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indicatif::ProgressStyle;
+
+    use super::{bytes_style, FALLBACK_BYTES_TEMPLATE};
+
+    #[test]
+    fn falls_back_to_builtin_bytes_template() {
+        assert!(ProgressStyle::default_bar().template(FALLBACK_BYTES_TEMPLATE).is_ok());
+
+        //A stray closing brace (e.g. from a translation) makes the template invalid
+        let broken_template = "{prefix}\n> {msg}: {bytes}/{total_bytes} }\n{wide_bar}";
+        assert!(ProgressStyle::default_bar().template(broken_template).is_err());
+        bytes_style(broken_template);
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+//Decides which progress changes are announced to screen readers, as announcing every update would drown out everything else
+//Progress is announced in 10% steps (at most every few seconds), while indeterminate progress is announced whenever its text changes
+#[derive(Default)]
+pub struct ProgressAnnouncer {
+    last_step: Option<AnnouncedStep>,
+    last_announcement: Option<Instant>
+}
+
+#[derive(Debug, PartialEq)]
+enum AnnouncedStep {
+    Fraction(u32),
+    Indeterminate(String)
+}
+
+impl ProgressAnnouncer {
+    const MIN_INTERVAL: Duration = Duration::from_secs(3);
+
+    //A fraction of None indicates indeterminate progress
+    pub fn should_announce(&mut self, txt: &str, fract: Option<f64>) -> bool {
+        self.should_announce_at(Instant::now(), txt, fract)
+    }
+
+    fn should_announce_at(&mut self, now: Instant, txt: &str, fract: Option<f64>) -> bool {
+        let step = match fract {
+            Some(fract) => AnnouncedStep::Fraction((fract.clamp(0_f64, 1_f64) * 10_f64) as u32),
+            None => AnnouncedStep::Indeterminate(String::from(txt))
+        };
+        if self.last_step.as_ref() == Some(&step) { return false; }
+
+        //Steps which are skipped because of the rate limit are picked up by a later update
+        if matches!(step, AnnouncedStep::Fraction(_)) && self.last_announcement.is_some_and(|t| now.duration_since(t) < ProgressAnnouncer::MIN_INTERVAL) { return false; }
+
+        self.last_step = Some(step);
+        self.last_announcement = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::ProgressAnnouncer;
+
+    #[test]
+    fn announces_progress_steps() {
+        let (mut announcer, start) = (ProgressAnnouncer::default(), Instant::now());
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        assert!(announcer.should_announce_at(at(0), "Downloading", Some(0.0)));
+        assert!(!announcer.should_announce_at(at(5), "Downloading", Some(0.05)));
+        assert!(announcer.should_announce_at(at(10), "Downloading", Some(0.15)));
+
+        //Skipped steps are announced once the rate limit has passed
+        assert!(!announcer.should_announce_at(at(11), "Downloading", Some(0.25)));
+        assert!(announcer.should_announce_at(at(13), "Downloading", Some(0.26)));
+        assert!(!announcer.should_announce_at(at(20), "Downloading", Some(0.29)));
+    }
+
+    #[test]
+    fn announces_indeterminate_text_changes() {
+        let (mut announcer, start) = (ProgressAnnouncer::default(), Instant::now());
+
+        assert!(announcer.should_announce_at(start, "Connecting", None));
+        assert!(!announcer.should_announce_at(start, "Connecting", None));
+        assert!(announcer.should_announce_at(start, "Verifying", None));
+        assert!(!announcer.should_announce_at(start, "Extracting", Some(0.0)));
+        assert!(announcer.should_announce_at(start + Duration::from_secs(3), "Extracting", Some(0.0)));
+    }
+}
@@ -0,0 +1,43 @@
+use std::{borrow::Cow, fs, io, path::Path, sync::OnceLock};
+
+use crate::cfg::{UI_BANNER_COLOR, UI_BANNER_EMBEDDED, UI_BANNER_FILE, UI_ICON_EMBEDDED, UI_ICON_FILE};
+
+//The product branding shown by the GUI dialogs
+//Images are PNG files, which the packaging app can either ship next to the apphost or embed into it
+pub struct Branding {
+    pub icon: Option<Cow<'static, [u8]>>,
+    pub banner: Option<Cow<'static, [u8]>>,
+    pub banner_color: Option<(u8, u8, u8)>
+}
+
+static BRANDING: OnceLock<Branding> = OnceLock::new();
+
+impl Branding {
+    fn load(install_dir: Option<&Path>) -> Branding {
+        Branding {
+            icon: load_image(install_dir, UI_ICON_FILE, UI_ICON_EMBEDDED),
+            banner: load_image(install_dir, UI_BANNER_FILE, UI_BANNER_EMBEDDED),
+            banner_color: UI_BANNER_COLOR
+        }
+    }
+}
+
+//Files shipped next to the apphost take precedence over the embedded images
+fn load_image(install_dir: Option<&Path>, file_name: &str, embedded: Option<&'static [u8]>) -> Option<Cow<'static, [u8]>> {
+    if let Some(install_dir) = install_dir {
+        match fs::read(install_dir.join(file_name)) {
+            Ok(data) => return Some(Cow::Owned(data)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => crate::log_warn!("Failed to read branding image '{file_name}': {err}")
+        }
+    }
+    embedded.map(Cow::Borrowed)
+}
+
+//Loads the branding shipped in the given install directory
+//Must be called before any dialogs are shown, otherwise only the embedded images are used
+pub fn init(install_dir: &Path) {
+    BRANDING.get_or_init(|| Branding::load(Some(install_dir)));
+}
+
+pub fn branding() -> &'static Branding { BRANDING.get_or_init(|| Branding::load(None)) }
@@ -0,0 +1,356 @@
+use std::{cell::Cell, ptr::{self, NonNull}};
+
+use crate::cfg::{UI_APP_NAME, UI_DESKTOP_ENTRY_ID};
+use crate::log_debug;
+
+use super::ffi::{self, to_cstring, GDBusConnection, GError, Gio, Variant};
+
+const LAUNCHER_ENTRY_IFACE: &str = "com.canonical.Unity.LauncherEntry";
+
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+const NOTIFICATIONS_IFACE: &str = "org.freedesktop.Notifications";
+
+//An owned reference to a GDBus connection
+pub(super) struct DBusConnection {
+    gio: &'static Gio,
+    conn: NonNull<GDBusConnection>
+}
+
+impl DBusConnection {
+    //Takes ownership of the given reference
+    pub(super) unsafe fn from_full(gio: &'static Gio, conn: *mut GDBusConnection) -> DBusConnection {
+        DBusConnection { gio, conn: NonNull::new(conn).expect("unexpected null GDBusConnection") }
+    }
+
+    pub fn emit_signal(&self, path: &str, iface: &str, signal: &str, params: &Variant) -> Result<(), String> {
+        let (path, iface, signal) = (to_cstring(path), to_cstring(iface), to_cstring(signal));
+        let mut err: *mut GError = ptr::null_mut();
+        unsafe {
+            if (self.gio.g_dbus_connection_emit_signal)(self.conn.as_ptr(), ptr::null(), path.as_ptr(), iface.as_ptr(), signal.as_ptr(), params.as_ptr(), &mut err) == ffi::FALSE {
+                return Err(ffi::take_error(err, self.gio.g_error_free));
+            }
+        }
+        Ok(())
+    }
+
+    //Sends the call without waiting for (or expecting) a reply
+    pub fn call(&self, dest: &str, path: &str, iface: &str, method: &str, params: &Variant) {
+        let (dest, path, iface, method) = (to_cstring(dest), to_cstring(path), to_cstring(iface), to_cstring(method));
+        unsafe {
+            (self.gio.g_dbus_connection_call)(self.conn.as_ptr(), dest.as_ptr(), path.as_ptr(), iface.as_ptr(), method.as_ptr(), params.as_ptr(), ptr::null(), ffi::G_DBUS_CALL_FLAGS_NONE, -1, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+        }
+    }
+
+    pub fn call_sync(&self, dest: &str, path: &str, iface: &str, method: &str, params: &Variant) -> Result<Variant, String> {
+        let (dest, path, iface, method) = (to_cstring(dest), to_cstring(path), to_cstring(iface), to_cstring(method));
+        let mut err: *mut GError = ptr::null_mut();
+        unsafe {
+            let res = (self.gio.g_dbus_connection_call_sync)(self.conn.as_ptr(), dest.as_ptr(), path.as_ptr(), iface.as_ptr(), method.as_ptr(), params.as_ptr(), ptr::null(), ffi::G_DBUS_CALL_FLAGS_NONE, -1, ptr::null_mut(), &mut err);
+            if res.is_null() { return Err(ffi::take_error(err, self.gio.g_error_free)); }
+            Ok(Variant::from_full(res))
+        }
+    }
+}
+
+impl Drop for DBusConnection {
+    fn drop(&mut self) {
+        //Messages which are still queued up are discarded once the connection is finalized, so flush them first
+        unsafe {
+            (self.gio.g_dbus_connection_flush_sync)(self.conn.as_ptr(), ptr::null_mut(), ptr::null_mut());
+            (self.gio.g_object_unref)(self.conn.as_ptr().cast());
+        }
+    }
+}
+
+pub(super) fn session_bus() -> Option<DBusConnection> {
+    let conn = ffi::gio().and_then(|gio| unsafe {
+        let mut err: *mut GError = ptr::null_mut();
+        let conn = (gio.g_bus_get_sync)(ffi::G_BUS_TYPE_SESSION, ptr::null_mut(), &mut err);
+        if conn.is_null() { return Err(ffi::take_error(err, gio.g_error_free)); }
+        Ok(DBusConnection::from_full(gio, conn))
+    });
+
+    match conn {
+        Ok(conn) => Some(conn),
+        Err(err) => {
+            log_debug!("Failed to connect to the D-Bus session bus: {err}");
+            None
+        }
+    }
+}
+
+//Desktop environments match launcher entries / notifications to apps by the ID of their .desktop file
+fn desktop_entry_id() -> String {
+    if let Some(id) = UI_DESKTOP_ENTRY_ID { return String::from(id); }
+
+    let exe_path = std::env::current_exe().ok();
+    exe_path.as_deref().and_then(|p| p.file_stem()).map_or_else(|| String::from("piton"), |s| s.to_string_lossy().into_owned())
+}
+
+//Publishes progress through the Unity launcher API, which is supported by e.g. Dash-to-Dock and KDE Plasma
+//The progress is hidden again once the entry is dropped
+pub struct LauncherEntry {
+    conn: DBusConnection,
+    object_path: String,
+    app_uri: String,
+    last_progress: Cell<Option<f64>>
+}
+
+impl LauncherEntry {
+    pub fn new(conn: DBusConnection) -> LauncherEntry {
+        LauncherEntry {
+            conn,
+            object_path: format!("/com/canonical/unity/launcherentry/{}", std::process::id()),
+            app_uri: format!("application://{}.desktop", desktop_entry_id()),
+            last_progress: Cell::new(None)
+        }
+    }
+
+    pub fn for_session_bus() -> Option<LauncherEntry> { session_bus().map(LauncherEntry::new) }
+
+    pub fn set_progress(&self, fract: f64) {
+        //Only publish noticeable changes, as this is called for every frame
+        if self.last_progress.get().is_some_and(|p| (p - fract).abs() < 0.01) { return; }
+        self.last_progress.set(Some(fract));
+
+        self.update(&format!("{{'progress': <{fract:.4}>, 'progress-visible': <true>}}"));
+    }
+
+    //The launcher entry protocol has no indeterminate progress, so hide the progress instead
+    pub fn hide_progress(&self) {
+        if self.last_progress.take().is_some() {
+            self.update("{'progress-visible': <false>}");
+        }
+    }
+
+    fn update(&self, props: &str) {
+        let (fmt, app_uri) = (to_cstring(&format!("(%s, {props})")), to_cstring(&self.app_uri));
+        let params = unsafe { Variant::from_full((self.conn.gio.g_variant_new_parsed)(fmt.as_ptr(), app_uri.as_ptr())) };
+        if let Err(err) = self.conn.emit_signal(&self.object_path, LAUNCHER_ENTRY_IFACE, "Update", &params) {
+            log_debug!("Failed to update the launcher entry: {err}");
+        }
+    }
+}
+
+impl Drop for LauncherEntry {
+    fn drop(&mut self) { self.hide_progress(); }
+}
+
+pub fn send_notification(summary: &str, body: &str) {
+    if let Some(conn) = session_bus() {
+        send_notification_on(&conn, summary, body);
+    }
+}
+
+fn send_notification_on(conn: &DBusConnection, summary: &str, body: &str) {
+    //The call is sent by GDBus' worker thread, so there's no need to wait for (or run a main loop for) the reply
+    let (app_name, summary, body, desktop_entry) = (to_cstring(UI_APP_NAME), to_cstring(summary), to_cstring(body), to_cstring(&desktop_entry_id()));
+    let params = unsafe {
+        Variant::from_full((conn.gio.g_variant_new_parsed)(c"(%s, %u, '', %s, %s, @as [], {'desktop-entry': <%s>}, -1)".as_ptr(),
+            app_name.as_ptr(), 0_u32, summary.as_ptr(), body.as_ptr(), desktop_entry.as_ptr()
+        ))
+    };
+    conn.call(NOTIFICATIONS_NAME, NOTIFICATIONS_PATH, NOTIFICATIONS_IFACE, "Notify", &params);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::c_char, io::{BufRead, BufReader}, process::{Child, Command, Stdio}, ptr, sync::{Arc, Mutex}, thread, time::{Duration, Instant}};
+
+    use crate::ui::gui::linux::tests::gui_tests_required;
+    use crate::ui::gui::linux::ffi::{self, to_cstring, GDBusConnection, GDBusInterfaceVTable, GDBusMethodInvocation, GError, GMainContext, GPointer, GVariant, Gio, Variant};
+
+    use super::{DBusConnection, LauncherEntry, send_notification_on, LAUNCHER_ENTRY_IFACE, NOTIFICATIONS_NAME, NOTIFICATIONS_PATH, NOTIFICATIONS_IFACE};
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    const NOTIFICATIONS_XML: &str = r#"
+        <node>
+            <interface name="org.freedesktop.Notifications">
+                <method name="Notify">
+                    <arg type="s" direction="in"/>
+                    <arg type="u" direction="in"/>
+                    <arg type="s" direction="in"/>
+                    <arg type="s" direction="in"/>
+                    <arg type="s" direction="in"/>
+                    <arg type="as" direction="in"/>
+                    <arg type="a{sv}" direction="in"/>
+                    <arg type="i" direction="in"/>
+                    <arg type="u" direction="out"/>
+                </method>
+            </interface>
+        </node>
+    "#;
+
+    //A private session bus, so that the tests don't need (or disturb) a real desktop
+    struct TestBus {
+        gio: &'static Gio,
+        daemon: Child,
+        address: String
+    }
+
+    impl TestBus {
+        fn spawn() -> Option<TestBus> {
+            let gio = ffi::gio().ok()?;
+            let mut daemon = Command::new("dbus-daemon").args(["--session", "--nofork", "--print-address"]).stdout(Stdio::piped()).spawn().ok()?;
+
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).ok()?;
+            Some(TestBus { gio, daemon, address: String::from(address.trim()) })
+        }
+
+        fn connect(&self) -> DBusConnection {
+            let flags = ffi::G_DBUS_CONNECTION_FLAGS_AUTHENTICATION_CLIENT | ffi::G_DBUS_CONNECTION_FLAGS_MESSAGE_BUS_CONNECTION;
+            let address = to_cstring(&self.address);
+            unsafe {
+                let mut err: *mut GError = ptr::null_mut();
+                let conn = (self.gio.g_dbus_connection_new_for_address_sync)(address.as_ptr(), flags, ptr::null_mut(), ptr::null_mut(), &mut err);
+                if conn.is_null() { panic!("failed to connect to the test bus: {}", ffi::take_error(err, self.gio.g_error_free)); }
+                DBusConnection::from_full(self.gio, conn)
+            }
+        }
+    }
+
+    impl Drop for TestBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    //A private main context, as the GTK tests might be using the global one
+    struct TestContext {
+        gio: &'static Gio,
+        ctx: *mut GMainContext
+    }
+
+    impl TestContext {
+        fn push(gio: &'static Gio) -> TestContext {
+            let ctx = unsafe { (gio.g_main_context_new)() };
+            unsafe { (gio.g_main_context_push_thread_default)(ctx) };
+            TestContext { gio, ctx }
+        }
+
+        fn iterate_until(&self, cond: impl Fn() -> bool) {
+            let deadline = Instant::now() + TIMEOUT;
+            while !cond() && Instant::now() < deadline {
+                if unsafe { (self.gio.g_main_context_iteration)(self.ctx, ffi::FALSE) } == ffi::FALSE {
+                    thread::sleep(Duration::from_millis(5));
+                }
+            }
+        }
+    }
+
+    impl Drop for TestContext {
+        fn drop(&mut self) {
+            unsafe {
+                (self.gio.g_main_context_pop_thread_default)(self.ctx);
+                (self.gio.g_main_context_unref)(self.ctx);
+            }
+        }
+    }
+
+    fn bus_call(conn: &DBusConnection, method: &str, params: &Variant) -> Variant {
+        conn.call_sync("org.freedesktop.DBus", "/org/freedesktop/DBus", "org.freedesktop.DBus", method, params).expect("failed to call the bus daemon")
+    }
+
+    //Passes shared state to GDBus callbacks, which drop their reference once they're unregistered
+    fn into_user_data<T>(data: &Arc<Mutex<T>>) -> GPointer { Arc::into_raw(data.clone()) as GPointer }
+
+    unsafe extern "C" fn drop_user_data<T>(data: GPointer) { drop(Arc::from_raw(data as *const Mutex<T>)); }
+
+    unsafe fn user_data<'a, T>(data: GPointer) -> &'a Mutex<T> { &*(data as *const Mutex<T>) }
+
+    type LauncherUpdate = (Option<String>, Option<f64>, Option<bool>);
+
+    #[test]
+    fn launcher_entry_updates() {
+        let Some(bus) = TestBus::spawn() else {
+            assert!(!gui_tests_required(), "GIO / dbus-daemon aren't available");
+            eprintln!("Skipping launcher entry test, as GIO / dbus-daemon aren't available");
+            return;
+        };
+
+        let ctx = TestContext::push(bus.gio);
+        let (listener, sender) = (bus.connect(), bus.connect());
+
+        //Listen for launcher entry updates, and ensure the subscription is active before sending any
+        unsafe extern "C" fn on_update(_: *mut GDBusConnection, _: *const c_char, _: *const c_char, _: *const c_char, _: *const c_char, params: *mut GVariant, data: GPointer) {
+            let params = Variant::from_none(params);
+            let props = params.child(1);
+            let prop = |key| props.as_ref().and_then(|p| p.lookup(key));
+            user_data::<Vec<LauncherUpdate>>(data).lock().unwrap().push((
+                params.child(0).and_then(|u| u.str()),
+                prop(c"progress").and_then(|p| p.f64()),
+                prop(c"progress-visible").and_then(|p| p.bool())
+            ));
+        }
+
+        let updates = Arc::new(Mutex::new(Vec::<LauncherUpdate>::new()));
+        let iface = to_cstring(LAUNCHER_ENTRY_IFACE);
+        unsafe {
+            (bus.gio.g_dbus_connection_signal_subscribe)(listener.conn.as_ptr(), ptr::null(), iface.as_ptr(), c"Update".as_ptr(), ptr::null(), ptr::null(), ffi::G_DBUS_SIGNAL_FLAGS_NONE,
+                on_update, into_user_data(&updates), Some(drop_user_data::<Vec<LauncherUpdate>>)
+            );
+        }
+        bus_call(&listener, "GetId", &Variant::parse(c"()"));
+
+        {
+            let entry = LauncherEntry::new(sender);
+            entry.set_progress(0.5);
+            entry.set_progress(0.501);
+        }
+
+        ctx.iterate_until(|| updates.lock().unwrap().len() >= 2);
+
+        //Insignificant changes are skipped, and the progress is hidden when the entry is dropped
+        let updates = updates.lock().unwrap();
+        assert_eq!(updates.len(), 2);
+        assert!(updates[0].0.as_deref().is_some_and(|uri| uri.starts_with("application://") && uri.ends_with(".desktop")));
+        assert_eq!(updates[0].1, Some(0.5));
+        assert_eq!(updates[0].2, Some(true));
+        assert_eq!(updates[1].2, Some(false));
+    }
+
+    #[test]
+    fn notification_sent() {
+        let Some(bus) = TestBus::spawn() else {
+            assert!(!gui_tests_required(), "GIO / dbus-daemon aren't available");
+            eprintln!("Skipping notification test, as GIO / dbus-daemon aren't available");
+            return;
+        };
+
+        let ctx = TestContext::push(bus.gio);
+        let (server, client) = (bus.connect(), bus.connect());
+
+        //Act as the notification server
+        unsafe extern "C" fn on_method_call(_: *mut GDBusConnection, _: *const c_char, _: *const c_char, _: *const c_char, _: *const c_char, params: *mut GVariant, invocation: *mut GDBusMethodInvocation, data: GPointer) {
+            let params = Variant::from_none(params);
+            user_data::<Vec<(Option<String>, Option<String>)>>(data).lock().unwrap().push((params.child(3).and_then(|s| s.str()), params.child(4).and_then(|b| b.str())));
+            (ffi::gio().unwrap().g_dbus_method_invocation_return_value)(invocation, Variant::parse(c"(uint32 1,)").as_ptr());
+        }
+
+        let calls = Arc::new(Mutex::new(Vec::<(Option<String>, Option<String>)>::new()));
+        let (xml, iface, path) = (to_cstring(NOTIFICATIONS_XML), to_cstring(NOTIFICATIONS_IFACE), to_cstring(NOTIFICATIONS_PATH));
+        unsafe {
+            let mut err: *mut GError = ptr::null_mut();
+            let node_info = (bus.gio.g_dbus_node_info_new_for_xml)(xml.as_ptr(), &mut err);
+            assert!(!node_info.is_null());
+            let iface_info = (bus.gio.g_dbus_node_info_lookup_interface)(node_info, iface.as_ptr());
+
+            let vtable = Box::leak(Box::new(GDBusInterfaceVTable { method_call: Some(on_method_call), get_property: ptr::null_mut(), set_property: ptr::null_mut(), padding: [ptr::null_mut(); 8] }));
+            let registration = (bus.gio.g_dbus_connection_register_object)(server.conn.as_ptr(), path.as_ptr(), iface_info, vtable, into_user_data(&calls), Some(drop_user_data::<Vec<(Option<String>, Option<String>)>>), &mut err);
+            assert_ne!(registration, 0);
+        }
+        bus_call(&server, "RequestName", &Variant::parse(&to_cstring(&format!("('{NOTIFICATIONS_NAME}', uint32 4)")))); //DBUS_NAME_FLAG_DO_NOT_QUEUE
+
+        send_notification_on(&client, "Setup finished", "The app is starting");
+        ctx.iterate_until(|| !calls.lock().unwrap().is_empty());
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0.as_deref(), Some("Setup finished"));
+        assert_eq!(calls[0].1.as_deref(), Some("The app is starting"));
+    }
+}
@@ -0,0 +1,313 @@
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::cfg::UI_APP_NAME;
+use crate::tr;
+use crate::ui::{ErrorReport, ProgressAction};
+
+use super::desktop::{session_bus, DBusConnection};
+use super::ffi::{self, to_cstring, Variant};
+
+const KDIALOG_PROGRESS_IFACE: &str = "org.kde.kdialog.ProgressDialog";
+
+//Dialogs can be shown through external tools when GTK isn't available
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogTool {
+    Zenity,
+    KDialog
+}
+
+impl DialogTool {
+    pub fn detect() -> Option<DialogTool> {
+        static DETECTED_TOOL: OnceLock<Option<DialogTool>> = OnceLock::new();
+        *DETECTED_TOOL.get_or_init(|| {
+            //Prefer kdialog on KDE, and zenity everywhere else
+            let is_kde = std::env::var("XDG_CURRENT_DESKTOP").is_ok_and(|d| d.split(':').any(|d| d.eq_ignore_ascii_case("KDE")));
+            let tools = if is_kde { [DialogTool::KDialog, DialogTool::Zenity] } else { [DialogTool::Zenity, DialogTool::KDialog] };
+            tools.into_iter().find(|tool| is_in_path(tool.command()))
+        })
+    }
+
+    fn command(self) -> &'static str {
+        match self {
+            DialogTool::Zenity => "zenity",
+            DialogTool::KDialog => "kdialog"
+        }
+    }
+
+    pub fn show_error_msgbox(self, report: &ErrorReport, can_retry: bool) -> Result<bool, Box<dyn Error>> {
+        let title = tr!("error.dialog-title", app = UI_APP_NAME);
+        let (retry_label, cancel_label) = (tr!("report.retry"), tr!("report.cancel"));
+
+        let mut cmd = Command::new(self.command());
+        match (self, can_retry) {
+            (DialogTool::Zenity, false) => cmd.args(["--error", "--title", &title, "--text", &escape_markup(&report.to_string())]),
+            (DialogTool::Zenity, true) => cmd.args(["--question", "--title", &title, "--text", &escape_markup(&report.to_string()), "--ok-label", &retry_label, "--cancel-label", &cancel_label]),
+            (DialogTool::KDialog, false) => cmd.args(["--title", &title, "--detailederror", &report.summary, &report.details]),
+            (DialogTool::KDialog, true) => cmd.args(["--title", &title, "--warningyesno", &report.to_string(), "--yes-label", &retry_label, "--no-label", &cancel_label])
+        };
+
+        //Both tools exit with a non-zero status code if the dialog is closed or the second button is pressed
+        let status = cmd.stdin(Stdio::null()).stdout(Stdio::null()).status()?;
+        Ok(can_retry && status.success())
+    }
+
+    pub fn run_progress_action<T>(self, descr: &str, action: impl FnOnce(&dyn ProgressAction) -> T) -> Result<Option<T>, Box<dyn Error>> {
+        self.run_progress_action_with(Command::new(self.command()), descr, action)
+    }
+
+    //Like run_progress_action, but runs the tool through the given command (e.g. a stub for testing)
+    fn run_progress_action_with<T>(self, cmd: Command, descr: &str, action: impl FnOnce(&dyn ProgressAction) -> T) -> Result<Option<T>, Box<dyn Error>> {
+        let dialog = match self {
+            DialogTool::Zenity => ToolProgressDialog::spawn_zenity(cmd, descr)?,
+            DialogTool::KDialog => ToolProgressDialog::spawn_kdialog(cmd, descr)?
+        };
+
+        //Run the action on the current thread, as the dialog is driven by the external process
+        let res = action(&dialog);
+        let cancelled = dialog.is_cancelled();
+        dialog.close();
+
+        Ok((!cancelled).then_some(res))
+    }
+}
+
+fn is_in_path(cmd: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else { return false; };
+    std::env::split_paths(&path).any(|dir| dir.join(cmd).is_file())
+}
+
+//zenity interprets all text as Pango markup
+fn escape_markup(txt: &str) -> String {
+    txt.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+enum ToolProgressDialog {
+    //zenity reads progress updates from its stdin, and exits once it's cancelled
+    Zenity {
+        child: Mutex<Child>,
+        stdin: Mutex<ChildStdin>,
+        last_update: Mutex<(String, Option<u32>)>
+    },
+
+    //kdialog forks into the background, and has to be driven through D-Bus
+    KDialog {
+        child: Mutex<Child>,
+        conn: DBusConnection,
+        service: String,
+        object_path: String,
+        last_update: Mutex<(String, Option<u32>)>,
+        cancel_check: Mutex<(Option<Instant>, bool)>
+    }
+}
+
+impl ToolProgressDialog {
+    fn spawn_zenity(mut cmd: Command, descr: &str) -> Result<ToolProgressDialog, Box<dyn Error>> {
+        let mut child = cmd
+            .args(["--progress", "--title", UI_APP_NAME, "--text", &escape_markup(descr), "--percentage=0"])
+            .stdin(Stdio::piped()).stdout(Stdio::null())
+            .spawn()?;
+
+        let stdin = child.stdin.take().unwrap();
+        Ok(ToolProgressDialog::Zenity { child: Mutex::new(child), stdin: Mutex::new(stdin), last_update: Mutex::new((String::new(), Some(0))) })
+    }
+
+    fn spawn_kdialog(mut cmd: Command, descr: &str) -> Result<ToolProgressDialog, Box<dyn Error>> {
+        let conn = session_bus().ok_or("kdialog progress dialogs require a D-Bus session bus")?;
+
+        //kdialog prints the D-Bus reference to the dialog ('<service> <object path>')
+        let mut child = cmd
+            .args(["--title", UI_APP_NAME, "--progressbar", descr, "100"])
+            .stdin(Stdio::null()).stdout(Stdio::piped())
+            .spawn()?;
+
+        let mut dialog_ref = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut dialog_ref)?;
+        let (service, object_path) = dialog_ref.trim().split_once(' ').ok_or_else(|| format!("unexpected kdialog progress dialog reference '{}'", dialog_ref.trim()))?;
+
+        let dialog = ToolProgressDialog::KDialog {
+            child: Mutex::new(child),
+            conn,
+            service: String::from(service),
+            object_path: String::from(object_path),
+            last_update: Mutex::new((String::new(), Some(0))),
+            cancel_check: Mutex::default()
+        };
+        dialog.call_kdialog("showCancelButton", &Variant::parse(c"(true,)"))?;
+        Ok(dialog)
+    }
+
+    fn call_kdialog(&self, method: &str, params: &Variant) -> Result<Variant, Box<dyn Error>> {
+        let ToolProgressDialog::KDialog { conn, service, object_path, .. } = self else { unreachable!() };
+        let (iface, method) = method.split_once('.').unwrap_or((KDIALOG_PROGRESS_IFACE, method));
+        Ok(conn.call_sync(service, object_path, iface, method, params)?)
+    }
+
+    fn close(self) {
+        match &self {
+            ToolProgressDialog::Zenity { child, .. } => {
+                let mut child = child.lock().unwrap();
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            ToolProgressDialog::KDialog { child, .. } => {
+                let _ = self.call_kdialog("close", &Variant::parse(c"()"));
+                let _ = child.lock().unwrap().wait();
+            }
+        }
+    }
+
+    fn set_kdialog_property(&self, prop: &str, val: u32) -> Result<Variant, Box<dyn Error>> {
+        self.call_kdialog("org.freedesktop.DBus.Properties.Set", &Variant::parse(&to_cstring(&format!("('{KDIALOG_PROGRESS_IFACE}', '{prop}', <int32 {val}>)"))))
+    }
+
+    //A percentage of None shows indeterminate progress
+    fn update(&self, txt: &str, percentage: Option<u32>) {
+        //Only forward actual changes, as the progress is reported for every downloaded chunk
+        let last_update = match self {
+            ToolProgressDialog::Zenity { last_update, .. } | ToolProgressDialog::KDialog { last_update, .. } => last_update
+        };
+        let mut last_update = last_update.lock().unwrap();
+        if last_update.0 == txt && last_update.1 == percentage { return; }
+        let was_indeterminate = last_update.1.is_none();
+        *last_update = (String::from(txt), percentage);
+
+        //Errors are ignored here, as they mean that the dialog has been closed, which is picked up as a cancellation
+        match self {
+            ToolProgressDialog::Zenity { stdin, .. } => {
+                //zenity switches its progress bar into / out of pulsating mode through 'pulsate:' lines
+                let mut stdin = stdin.lock().unwrap();
+                let txt = escape_markup(&txt.replace('\n', " "));
+                let _ = match percentage {
+                    Some(percentage) if was_indeterminate => write!(stdin, "pulsate:false\n# {txt}\n{percentage}\n"),
+                    Some(percentage) => write!(stdin, "# {txt}\n{percentage}\n"),
+                    None => write!(stdin, "pulsate:true\n# {txt}\n")
+                };
+            }
+            ToolProgressDialog::KDialog { .. } => {
+                let gio = ffi::gio().unwrap();
+                let txt = to_cstring(txt);
+                let _ = self.call_kdialog("setLabelText", &unsafe { Variant::from_full((gio.g_variant_new_parsed)(c"(%s,)".as_ptr(), txt.as_ptr())) });
+
+                for (prop, val) in kdialog_progress_props(percentage, was_indeterminate) {
+                    let _ = self.set_kdialog_property(prop, val);
+                }
+            }
+        }
+    }
+}
+
+//Returns the kdialog progress bar properties to set for the given progress update
+//Qt progress bars show a busy indicator if their maximum is zero
+fn kdialog_progress_props(percentage: Option<u32>, was_indeterminate: bool) -> Vec<(&'static str, u32)> {
+    match percentage {
+        Some(percentage) if was_indeterminate => vec![("maximum", 100), ("value", percentage)],
+        Some(percentage) => vec![("value", percentage)],
+        None => vec![("maximum", 0)]
+    }
+}
+
+impl ProgressAction for ToolProgressDialog {
+    fn set_progress(&self, txt: &str, fract: f64) {
+        self.update(txt, Some((fract.clamp(0_f64, 1_f64) * 100_f64) as u32));
+    }
+
+    fn set_indeterminate_progress(&self, txt: &str) { self.update(txt, None); }
+
+    fn is_cancelled(&self) -> bool {
+        match self {
+            ToolProgressDialog::Zenity { child, .. } => child.lock().unwrap().try_wait().is_ok_and(|status| status.is_some()),
+            ToolProgressDialog::KDialog { cancel_check, .. } => {
+                //Rate limit the D-Bus calls, as this is checked for every downloaded chunk
+                let mut cancel_check = cancel_check.lock().unwrap();
+                if cancel_check.1 || cancel_check.0.is_some_and(|t| t.elapsed() < Duration::from_millis(100)) { return cancel_check.1; }
+
+                cancel_check.1 = match self.call_kdialog("wasCancelled", &Variant::parse(c"()")) {
+                    Ok(res) => res.child(0).and_then(|c| c.bool()).unwrap_or(false),
+                    Err(_) => true
+                };
+                cancel_check.0 = Some(Instant::now());
+                cancel_check.1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt, path::Path, process::Command, thread, time::{Duration, Instant}};
+
+    use super::{kdialog_progress_props, DialogTool};
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    //Records the arguments and stdin lines it receives, and exits (like a cancelled dialog) once it's told to
+    const ZENITY_STUB: &str = r##"#!/bin/sh
+dir="$(dirname "$0")"
+printf '%s\n' "$@" > "$dir/args"
+while IFS= read -r line; do
+    printf '%s\n' "$line" >> "$dir/stdin"
+    [ "$line" = "# cancel" ] && exit 1
+done
+"##;
+
+    fn wait_until(cond: impl Fn() -> bool) {
+        let deadline = Instant::now() + TIMEOUT;
+        while !cond() {
+            assert!(Instant::now() < deadline, "timed out waiting for the zenity stub");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn read_lines(path: &Path) -> Vec<String> {
+        fs::read_to_string(path).unwrap_or_default().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn zenity_progress_protocol() {
+        let stub_dir = std::env::temp_dir().join(format!("piton-zenity-stub-{}", std::process::id()));
+        fs::create_dir_all(&stub_dir).unwrap();
+        let stub_path = stub_dir.join("zenity");
+        fs::write(&stub_path, ZENITY_STUB).unwrap();
+        fs::set_permissions(&stub_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let stdin_path = stub_dir.join("stdin");
+        let res = DialogTool::Zenity.run_progress_action_with(Command::new(&stub_path), "Setting <up>", |act| {
+            act.set_progress("Downloading", 0.5);
+            act.set_progress("Downloading", 0.5);
+            act.set_indeterminate_progress("Unpacking");
+            act.set_progress("Unpacking <archive>", 0.25);
+
+            wait_until(|| read_lines(&stdin_path).len() >= 7);
+            assert!(!act.is_cancelled());
+
+            act.set_progress("cancel", 1_f64);
+            wait_until(|| act.is_cancelled());
+        }).unwrap();
+        assert!(res.is_none());
+
+        //Unchanged updates are skipped, and the progress bar is switched into / out of pulsating mode
+        assert_eq!(read_lines(&stdin_path), [
+            "# Downloading", "50",
+            "pulsate:true", "# Unpacking",
+            "pulsate:false", "# Unpacking &lt;archive&gt;", "25",
+            "# cancel"
+        ]);
+
+        let args = read_lines(&stub_dir.join("args"));
+        assert!(args.iter().any(|a| a == "--progress") && args.iter().any(|a| a == "--percentage=0"));
+        assert!(args.windows(2).any(|a| a[0] == "--text" && a[1] == "Setting &lt;up&gt;"));
+
+        fs::remove_dir_all(stub_dir).unwrap();
+    }
+
+    #[test]
+    fn kdialog_progress_maximum() {
+        assert_eq!(kdialog_progress_props(Some(50), false), [("value", 50)]);
+        assert_eq!(kdialog_progress_props(None, false), [("maximum", 0)]);
+        assert_eq!(kdialog_progress_props(Some(25), true), [("maximum", 100), ("value", 25)]);
+    }
+}
@@ -0,0 +1,348 @@
+//Thin bindings for the parts of GTK / GIO we use, which are loaded at runtime using dlopen
+//This allows the apphost to run (and fall back to other dialogs / the CLI) on systems which don't have GTK installed
+#![allow(dead_code)] //Some declarations are only used by the tests
+use std::ffi::{c_char, c_int, c_uint, c_void, CStr, CString};
+use std::ptr::NonNull;
+use std::sync::OnceLock;
+
+pub type GBoolean = c_int;
+pub type GPointer = *mut c_void;
+pub type GType = usize;
+
+pub const FALSE: GBoolean = 0;
+pub const TRUE: GBoolean = 1;
+
+pub const G_SOURCE_REMOVE: GBoolean = 0;
+pub const G_SOURCE_CONTINUE: GBoolean = 1;
+
+macro_rules! opaque_types {
+    ($($name:ident),*) => { $(#[repr(C)] pub struct $name { _private: [u8; 0] })* };
+}
+
+opaque_types!(GVariant, GDBusConnection, GDBusMethodInvocation, GDBusNodeInfo, GDBusInterfaceInfo, GMainContext, GtkWidget, GtkTextBuffer, GtkClipboard, GtkCssProvider, GtkStyleContext, GdkPixbuf, GdkPixbufLoader, AtkObject);
+
+#[repr(C)]
+pub struct GError {
+    pub domain: u32,
+    pub code: c_int,
+    pub message: *mut c_char
+}
+
+#[repr(C)]
+pub struct GList {
+    pub data: GPointer,
+    pub next: *mut GList,
+    pub prev: *mut GList
+}
+
+#[repr(C)]
+pub struct GDBusInterfaceVTable {
+    pub method_call: Option<unsafe extern "C" fn(*mut GDBusConnection, *const c_char, *const c_char, *const c_char, *const c_char, *mut GVariant, *mut GDBusMethodInvocation, GPointer)>,
+    pub get_property: GPointer,
+    pub set_property: GPointer,
+    pub padding: [GPointer; 8]
+}
+
+//GIO enums / flags
+pub const G_BUS_TYPE_SESSION: c_int = 2;
+pub const G_DBUS_CALL_FLAGS_NONE: c_int = 0;
+pub const G_DBUS_CONNECTION_FLAGS_AUTHENTICATION_CLIENT: c_int = 1 << 0;
+pub const G_DBUS_CONNECTION_FLAGS_MESSAGE_BUS_CONNECTION: c_int = 1 << 3;
+pub const G_DBUS_SIGNAL_FLAGS_NONE: c_int = 0;
+
+//GTK enums / flags
+pub const GTK_DIALOG_MODAL: c_int = 1 << 0;
+pub const GTK_DIALOG_DESTROY_WITH_PARENT: c_int = 1 << 1;
+
+pub const GTK_MESSAGE_QUESTION: c_int = 2;
+pub const GTK_MESSAGE_ERROR: c_int = 3;
+
+pub const GTK_BUTTONS_NONE: c_int = 0;
+pub const GTK_BUTTONS_YES_NO: c_int = 4;
+
+pub const GTK_RESPONSE_NONE: c_int = -1;
+pub const GTK_RESPONSE_CANCEL: c_int = -6;
+pub const GTK_RESPONSE_CLOSE: c_int = -7;
+pub const GTK_RESPONSE_YES: c_int = -8;
+pub const GTK_RESPONSE_NO: c_int = -9;
+
+pub const GTK_ORIENTATION_HORIZONTAL: c_int = 0;
+pub const GTK_WRAP_WORD_CHAR: c_int = 3;
+
+pub const GTK_STYLE_PROVIDER_PRIORITY_APPLICATION: c_uint = 600;
+pub const GDK_INTERP_BILINEAR: c_int = 2;
+
+//GDK_SELECTION_CLIPBOARD is a predefined atom, which is just its index cast to a pointer
+pub const GDK_SELECTION_CLIPBOARD: GPointer = 69 as GPointer;
+
+pub type GSourceFunc = unsafe extern "C" fn(GPointer) -> GBoolean;
+pub type GDestroyNotify = unsafe extern "C" fn(GPointer);
+pub type GDBusSignalCallback = unsafe extern "C" fn(*mut GDBusConnection, *const c_char, *const c_char, *const c_char, *const c_char, *mut GVariant, GPointer);
+pub type GtkTickCallback = unsafe extern "C" fn(*mut GtkWidget, GPointer, GPointer) -> GBoolean;
+
+//Declares a table of function pointers, which are resolved from the given library when it's loaded
+//Symbols of the library's dependencies (e.g. GLib for GIO) are also resolved through its handle
+//Optional symbols are only present in newer library versions, and are None if they can't be resolved
+macro_rules! dynamic_library {
+    ($name:ident($soname:literal) { $($(#[$attr:meta])* fn $fname:ident($($args:tt)*) $(-> $ret:ty)?;)* } $(optional { $(fn $oname:ident($($oargs:tt)*) $(-> $oret:ty)?;)* })?) => {
+        pub struct $name {
+            $($(#[$attr])* pub $fname: unsafe extern "C" fn($($args)*) $(-> $ret)?,)*
+            $($(pub $oname: Option<unsafe extern "C" fn($($oargs)*) $(-> $oret)?>,)*)?
+        }
+
+        impl $name {
+            fn load() -> Result<$name, String> {
+                unsafe {
+                    //The library is never unloaded again
+                    let handle = libc::dlopen(concat!($soname, "\0").as_ptr().cast(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+                    if handle.is_null() { return Err(dl_error()); }
+
+                    Ok($name {
+                        $($(#[$attr])* $fname: {
+                            let sym = libc::dlsym(handle, concat!(stringify!($fname), "\0").as_ptr().cast());
+                            if sym.is_null() { return Err(dl_error()); }
+                            std::mem::transmute::<*mut c_void, unsafe extern "C" fn($($args)*) $(-> $ret)?>(sym)
+                        },)*
+                        $($($oname: {
+                            let sym = libc::dlsym(handle, concat!(stringify!($oname), "\0").as_ptr().cast());
+                            (!sym.is_null()).then(|| std::mem::transmute::<*mut c_void, unsafe extern "C" fn($($oargs)*) $(-> $oret)?>(sym))
+                        },)*)?
+                    })
+                }
+            }
+        }
+    };
+}
+
+fn dl_error() -> String {
+    let err = unsafe { libc::dlerror() };
+    if err.is_null() { return String::from("unknown dynamic loader error"); }
+    unsafe { CStr::from_ptr(err) }.to_string_lossy().into_owned()
+}
+
+dynamic_library!(Gio("libgio-2.0.so.0") {
+    fn g_error_free(*mut GError);
+    fn g_object_unref(GPointer);
+
+    fn g_variant_new_parsed(*const c_char, ...) -> *mut GVariant;
+    fn g_variant_ref(*mut GVariant) -> *mut GVariant;
+    fn g_variant_take_ref(*mut GVariant) -> *mut GVariant;
+    fn g_variant_unref(*mut GVariant);
+    fn g_variant_classify(*mut GVariant) -> c_int;
+    fn g_variant_get_boolean(*mut GVariant) -> GBoolean;
+    fn g_variant_get_double(*mut GVariant) -> f64;
+    fn g_variant_get_string(*mut GVariant, *mut usize) -> *const c_char;
+    fn g_variant_n_children(*mut GVariant) -> usize;
+    fn g_variant_get_child_value(*mut GVariant, usize) -> *mut GVariant;
+    fn g_variant_lookup_value(*mut GVariant, *const c_char, *const c_void) -> *mut GVariant;
+
+    fn g_bus_get_sync(c_int, GPointer, *mut *mut GError) -> *mut GDBusConnection;
+    fn g_dbus_connection_emit_signal(*mut GDBusConnection, *const c_char, *const c_char, *const c_char, *const c_char, *mut GVariant, *mut *mut GError) -> GBoolean;
+    fn g_dbus_connection_call(*mut GDBusConnection, *const c_char, *const c_char, *const c_char, *const c_char, *mut GVariant, *const c_void, c_int, c_int, GPointer, GPointer, GPointer);
+    fn g_dbus_connection_flush_sync(*mut GDBusConnection, GPointer, *mut *mut GError) -> GBoolean;
+    fn g_dbus_connection_call_sync(*mut GDBusConnection, *const c_char, *const c_char, *const c_char, *const c_char, *mut GVariant, *const c_void, c_int, c_int, GPointer, *mut *mut GError) -> *mut GVariant;
+
+    #[cfg(test)] fn g_dbus_connection_new_for_address_sync(*const c_char, c_int, GPointer, GPointer, *mut *mut GError) -> *mut GDBusConnection;
+    #[cfg(test)] fn g_dbus_connection_signal_subscribe(*mut GDBusConnection, *const c_char, *const c_char, *const c_char, *const c_char, *const c_char, c_int, GDBusSignalCallback, GPointer, Option<GDestroyNotify>) -> c_uint;
+    #[cfg(test)] fn g_dbus_connection_register_object(*mut GDBusConnection, *const c_char, *mut GDBusInterfaceInfo, *const GDBusInterfaceVTable, GPointer, Option<GDestroyNotify>, *mut *mut GError) -> c_uint;
+    #[cfg(test)] fn g_dbus_method_invocation_return_value(*mut GDBusMethodInvocation, *mut GVariant);
+    #[cfg(test)] fn g_dbus_node_info_new_for_xml(*const c_char, *mut *mut GError) -> *mut GDBusNodeInfo;
+    #[cfg(test)] fn g_dbus_node_info_lookup_interface(*mut GDBusNodeInfo, *const c_char) -> *mut GDBusInterfaceInfo;
+    #[cfg(test)] fn g_main_context_new() -> *mut GMainContext;
+    #[cfg(test)] fn g_main_context_push_thread_default(*mut GMainContext);
+    #[cfg(test)] fn g_main_context_pop_thread_default(*mut GMainContext);
+    #[cfg(test)] fn g_main_context_iteration(*mut GMainContext, GBoolean) -> GBoolean;
+    #[cfg(test)] fn g_main_context_unref(*mut GMainContext);
+});
+
+dynamic_library!(Gtk("libgtk-3.so.0") {
+    fn gtk_init_check(*mut c_int, *mut *mut *mut c_char) -> GBoolean;
+    fn g_set_prgname(*const c_char);
+    fn g_get_application_name() -> *const c_char;
+    fn g_filename_to_uri(*const c_char, *const c_char, *mut *mut GError) -> *mut c_char;
+    fn g_free(GPointer);
+    fn g_error_free(*mut GError);
+    fn g_object_ref(GPointer) -> GPointer;
+    fn g_object_unref(GPointer);
+
+    fn gtk_widget_show_all(*mut GtkWidget);
+    fn gtk_widget_destroy(*mut GtkWidget);
+    fn gtk_widget_set_size_request(*mut GtkWidget, c_int, c_int);
+    fn gtk_widget_set_margin_start(*mut GtkWidget, c_int);
+    fn gtk_widget_set_margin_end(*mut GtkWidget, c_int);
+    fn gtk_widget_set_margin_top(*mut GtkWidget, c_int);
+    fn gtk_widget_set_margin_bottom(*mut GtkWidget, c_int);
+    fn gtk_widget_set_sensitive(*mut GtkWidget, GBoolean);
+    fn gtk_widget_grab_focus(*mut GtkWidget);
+    fn gtk_widget_get_accessible(*mut GtkWidget) -> *mut AtkObject;
+    fn atk_object_set_name(*mut AtkObject, *const c_char);
+    fn gtk_widget_add_tick_callback(*mut GtkWidget, GtkTickCallback, GPointer, Option<GDestroyNotify>) -> c_uint;
+
+    fn gtk_window_set_title(*mut GtkWidget, *const c_char);
+    fn gtk_window_set_resizable(*mut GtkWidget, GBoolean);
+    fn gtk_window_set_wmclass(*mut GtkWidget, *const c_char, *const c_char);
+    fn gtk_container_add(*mut GtkWidget, *mut GtkWidget);
+    fn gtk_box_new(c_int, c_int) -> *mut GtkWidget;
+    fn gtk_box_set_spacing(*mut GtkWidget, c_int);
+
+    fn gtk_label_new(*const c_char) -> *mut GtkWidget;
+    fn gtk_label_set_text(*mut GtkWidget, *const c_char);
+    fn gtk_label_set_markup(*mut GtkWidget, *const c_char);
+    fn gtk_progress_bar_new() -> *mut GtkWidget;
+    fn gtk_progress_bar_set_fraction(*mut GtkWidget, f64);
+    fn gtk_progress_bar_pulse(*mut GtkWidget);
+    fn gtk_progress_bar_set_pulse_step(*mut GtkWidget, f64);
+    fn gtk_text_view_new() -> *mut GtkWidget;
+    fn gtk_text_view_set_editable(*mut GtkWidget, GBoolean);
+    fn gtk_text_view_set_monospace(*mut GtkWidget, GBoolean);
+    fn gtk_text_view_set_wrap_mode(*mut GtkWidget, c_int);
+    fn gtk_text_view_get_buffer(*mut GtkWidget) -> *mut GtkTextBuffer;
+    fn gtk_text_buffer_set_text(*mut GtkTextBuffer, *const c_char, c_int);
+    fn gtk_scrolled_window_new(GPointer, GPointer) -> *mut GtkWidget;
+    fn gtk_expander_new(*const c_char) -> *mut GtkWidget;
+
+    fn gtk_dialog_new() -> *mut GtkWidget;
+    fn gtk_dialog_add_button(*mut GtkWidget, *const c_char, c_int) -> *mut GtkWidget;
+    fn gtk_dialog_set_default_response(*mut GtkWidget, c_int);
+    fn gtk_dialog_get_content_area(*mut GtkWidget) -> *mut GtkWidget;
+    fn gtk_dialog_run(*mut GtkWidget) -> c_int;
+    fn gtk_dialog_response(*mut GtkWidget, c_int);
+    fn gtk_message_dialog_new(*mut GtkWidget, c_int, c_int, c_int, *const c_char, ...) -> *mut GtkWidget;
+    fn gtk_message_dialog_get_message_area(*mut GtkWidget) -> *mut GtkWidget;
+
+    fn gtk_clipboard_get(GPointer) -> *mut GtkClipboard;
+    fn gtk_clipboard_set_text(*mut GtkClipboard, *const c_char, c_int);
+    fn gtk_clipboard_store(*mut GtkClipboard);
+    fn gtk_show_uri_on_window(*mut GtkWidget, *const c_char, u32, *mut *mut GError) -> GBoolean;
+    fn gtk_get_current_event_time() -> u32;
+
+    fn gdk_pixbuf_loader_new() -> *mut GdkPixbufLoader;
+    fn gdk_pixbuf_loader_write(*mut GdkPixbufLoader, *const u8, usize, *mut *mut GError) -> GBoolean;
+    fn gdk_pixbuf_loader_close(*mut GdkPixbufLoader, *mut *mut GError) -> GBoolean;
+    fn gdk_pixbuf_loader_get_pixbuf(*mut GdkPixbufLoader) -> *mut GdkPixbuf;
+    fn gdk_pixbuf_get_width(*mut GdkPixbuf) -> c_int;
+    fn gdk_pixbuf_get_height(*mut GdkPixbuf) -> c_int;
+    fn gdk_pixbuf_scale_simple(*mut GdkPixbuf, c_int, c_int, c_int) -> *mut GdkPixbuf;
+    fn gtk_window_set_default_icon(*mut GdkPixbuf);
+    fn gtk_image_new_from_pixbuf(*mut GdkPixbuf) -> *mut GtkWidget;
+    fn gtk_event_box_new() -> *mut GtkWidget;
+    fn gtk_css_provider_new() -> *mut GtkCssProvider;
+    fn gtk_css_provider_load_from_data(*mut GtkCssProvider, *const c_char, isize, *mut *mut GError) -> GBoolean;
+    fn gtk_widget_get_style_context(*mut GtkWidget) -> *mut GtkStyleContext;
+    fn gtk_style_context_add_provider(*mut GtkStyleContext, *mut GtkCssProvider, c_uint);
+
+    #[cfg(test)] fn g_object_get(GPointer, *const c_char, ...);
+    #[cfg(test)] fn g_type_check_instance_is_a(GPointer, GType) -> GBoolean;
+    #[cfg(test)] fn g_timeout_add(c_uint, GSourceFunc, GPointer) -> c_uint;
+    #[cfg(test)] fn g_list_free(*mut GList);
+    #[cfg(test)] fn gtk_events_pending() -> GBoolean;
+    #[cfg(test)] fn gtk_main_iteration() -> GBoolean;
+    #[cfg(test)] fn gtk_window_list_toplevels() -> *mut GList;
+    #[cfg(test)] fn gtk_window_close(*mut GtkWidget);
+    #[cfg(test)] fn gtk_widget_is_visible(*mut GtkWidget) -> GBoolean;
+    #[cfg(test)] fn gtk_widget_is_sensitive(*mut GtkWidget) -> GBoolean;
+    #[cfg(test)] fn gtk_container_get_children(*mut GtkWidget) -> *mut GList;
+    #[cfg(test)] fn gtk_label_get_text(*mut GtkWidget) -> *const c_char;
+    #[cfg(test)] fn gtk_button_get_label(*mut GtkWidget) -> *const c_char;
+    #[cfg(test)] fn gtk_button_clicked(*mut GtkWidget);
+    #[cfg(test)] fn gtk_progress_bar_get_fraction(*mut GtkWidget) -> f64;
+    #[cfg(test)] fn gtk_container_get_type() -> GType;
+    #[cfg(test)] fn gtk_dialog_get_type() -> GType;
+    #[cfg(test)] fn gtk_message_dialog_get_type() -> GType;
+    #[cfg(test)] fn gtk_label_get_type() -> GType;
+    #[cfg(test)] fn gtk_button_get_type() -> GType;
+    #[cfg(test)] fn gtk_progress_bar_get_type() -> GType;
+    #[cfg(test)] fn gtk_text_view_get_type() -> GType;
+    #[cfg(test)] fn gtk_image_get_type() -> GType;
+    #[cfg(test)] fn gtk_widget_is_focus(*mut GtkWidget) -> GBoolean;
+    #[cfg(test)] fn atk_object_get_name(*mut AtkObject) -> *const c_char;
+} optional {
+    //Added in ATK 2.46
+    fn atk_object_announce(*mut AtkObject, *const c_char);
+});
+
+pub fn gio() -> Result<&'static Gio, String> {
+    static GIO: OnceLock<Result<Gio, String>> = OnceLock::new();
+    GIO.get_or_init(Gio::load).as_ref().map_err(Clone::clone)
+}
+
+pub fn gtk() -> Result<&'static Gtk, String> {
+    static GTK: OnceLock<Result<Gtk, String>> = OnceLock::new();
+    GTK.get_or_init(Gtk::load).as_ref().map_err(Clone::clone)
+}
+
+//Interior NUL bytes can't be passed to C, so they are dropped
+pub fn to_cstring(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap()
+}
+
+//Takes ownership of a GError, and returns its message
+pub unsafe fn take_error(err: *mut GError, error_free: unsafe extern "C" fn(*mut GError)) -> String {
+    let msg = CStr::from_ptr((*err).message).to_string_lossy().into_owned();
+    error_free(err);
+    msg
+}
+
+//Takes ownership of a GLib-allocated string
+pub unsafe fn take_string(s: *mut c_char, free: unsafe extern "C" fn(GPointer)) -> String {
+    let res = CStr::from_ptr(s).to_string_lossy().into_owned();
+    free(s.cast());
+    res
+}
+
+//An owned (non-floating) reference to a GVariant
+pub struct Variant(NonNull<GVariant>);
+
+impl Variant {
+    //Takes ownership of the given reference, converting it to a full one if it's floating
+    pub unsafe fn from_full(ptr: *mut GVariant) -> Variant {
+        Variant(NonNull::new((Self::gio().g_variant_take_ref)(ptr)).expect("unexpected null GVariant"))
+    }
+
+    pub unsafe fn from_none(ptr: *mut GVariant) -> Variant {
+        Variant::from_full((Self::gio().g_variant_ref)(ptr))
+    }
+
+    //Creates a variant from its text form (see GVariant's format strings for the supported placeholders)
+    pub fn parse(txt: &CStr) -> Variant {
+        unsafe { Variant::from_full((Self::gio().g_variant_new_parsed)(txt.as_ptr())) }
+    }
+
+    pub fn as_ptr(&self) -> *mut GVariant { self.0.as_ptr() }
+
+    fn gio() -> &'static Gio { gio().unwrap() }
+
+    fn class(&self) -> u8 { unsafe { (Self::gio().g_variant_classify)(self.as_ptr()) as u8 } }
+
+    pub fn child(&self, idx: usize) -> Option<Variant> {
+        unsafe {
+            if !matches!(self.class(), b'(' | b'a' | b'{' | b'v') || idx >= (Self::gio().g_variant_n_children)(self.as_ptr()) { return None; }
+            Some(Variant::from_full((Self::gio().g_variant_get_child_value)(self.as_ptr(), idx)))
+        }
+    }
+
+    //Looks up a value in a dictionary, unboxing it if it's a variant
+    pub fn lookup(&self, key: &CStr) -> Option<Variant> {
+        if self.class() != b'a' { return None; }
+        let val = unsafe { (Self::gio().g_variant_lookup_value)(self.as_ptr(), key.as_ptr(), std::ptr::null()) };
+        (!val.is_null()).then(|| unsafe { Variant::from_full(val) })
+    }
+
+    pub fn bool(&self) -> Option<bool> {
+        (self.class() == b'b').then(|| unsafe { (Self::gio().g_variant_get_boolean)(self.as_ptr()) != FALSE })
+    }
+
+    pub fn f64(&self) -> Option<f64> {
+        (self.class() == b'd').then(|| unsafe { (Self::gio().g_variant_get_double)(self.as_ptr()) })
+    }
+
+    pub fn str(&self) -> Option<String> {
+        (self.class() == b's').then(|| unsafe { CStr::from_ptr((Self::gio().g_variant_get_string)(self.as_ptr(), std::ptr::null_mut())).to_string_lossy().into_owned() })
+    }
+}
+
+impl Drop for Variant {
+    fn drop(&mut self) {
+        unsafe { (Self::gio().g_variant_unref)(self.as_ptr()) }
+    }
+}
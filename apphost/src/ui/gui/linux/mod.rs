@@ -0,0 +1,858 @@
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::ffi::{c_int, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+use std::sync::{Mutex, OnceLock};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use crate::cfg::{UI_APP_NAME, UI_ERROR_REPORT_URL, UI_ERROR_SHOW_LOG_FOLDER};
+use crate::tr;
+use crate::ui::gui::{accessibility::ProgressAnnouncer, branding::branding};
+use crate::ui::{ErrorReport, ProgressAction};
+
+mod ffi;
+use ffi::{to_cstring, GBoolean, GError, GPointer, GdkPixbuf, Gtk, GtkWidget};
+
+mod desktop;
+pub use desktop::send_notification;
+use desktop::LauncherEntry;
+
+mod dialog_tool;
+use dialog_tool::DialogTool;
+
+fn init_gtk() -> Result<&'static Gtk, String> {
+    static GTK_INIT_LOCK: OnceLock<Result<ThreadId, String>> = OnceLock::new();
+
+    //Ensure GTK is only loaded / initialized once
+    let gtk_main_thread = GTK_INIT_LOCK.get_or_init(|| {
+        let gtk = ffi::gtk()?;
+        unsafe {
+            if (gtk.gtk_init_check)(ptr::null_mut(), ptr::null_mut()) == ffi::FALSE { return Err(String::from("failed to initialize GTK")); }
+            (gtk.g_set_prgname)(to_cstring(UI_APP_NAME).as_ptr());
+            set_default_icon(gtk);
+        }
+        Ok(thread::current().id())
+    }).clone()?;
+
+    //Ensure that this call comes from the same thread which first initialized GTK (and as such became the main thread)
+    assert_eq!(thread::current().id(), gtk_main_thread);
+    ffi::gtk()
+}
+
+//GTK is loaded at runtime, so dialogs can only be shown if it (or one of the dialog tools) is available
+pub fn is_supported() -> bool {
+    init_gtk().is_ok() || DialogTool::detect().is_some()
+}
+
+unsafe fn set_window_wmclass(gtk: &Gtk, window: *mut GtkWidget) {
+    let name = (gtk.g_get_application_name)();
+    (gtk.gtk_window_set_wmclass)(window, name, name);
+}
+
+//Toplevel windows are owned by GTK itself, so hold an additional reference in case they are destroyed from under us
+unsafe fn ref_toplevel(gtk: &Gtk, window: *mut GtkWidget) -> *mut GtkWidget {
+    (gtk.g_object_ref)(window.cast());
+    window
+}
+
+unsafe fn destroy_toplevel(gtk: &Gtk, window: *mut GtkWidget) {
+    (gtk.gtk_widget_destroy)(window);
+    (gtk.g_object_unref)(window.cast());
+}
+
+unsafe fn set_margin(gtk: &Gtk, widget: *mut GtkWidget, margin: c_int) {
+    (gtk.gtk_widget_set_margin_start)(widget, margin);
+    (gtk.gtk_widget_set_margin_end)(widget, margin);
+    (gtk.gtk_widget_set_margin_top)(widget, margin);
+    (gtk.gtk_widget_set_margin_bottom)(widget, margin);
+}
+
+unsafe fn add_button(gtk: &Gtk, dialog: *mut GtkWidget, label: &str, resp: c_int) -> *mut GtkWidget {
+    (gtk.gtk_dialog_add_button)(dialog, to_cstring(label).as_ptr(), resp)
+}
+
+//Returns a new reference to the decoded image
+unsafe fn load_pixbuf(gtk: &Gtk, data: &[u8]) -> Result<*mut GdkPixbuf, String> {
+    let loader = (gtk.gdk_pixbuf_loader_new)();
+    let mut err: *mut GError = ptr::null_mut();
+    let res = if (gtk.gdk_pixbuf_loader_write)(loader, data.as_ptr(), data.len(), &mut err) != ffi::FALSE {
+        //Always close the loader, even if writing failed
+        (gtk.gdk_pixbuf_loader_close)(loader, &mut err)
+    } else {
+        (gtk.gdk_pixbuf_loader_close)(loader, ptr::null_mut());
+        ffi::FALSE
+    };
+
+    let pixbuf = (gtk.gdk_pixbuf_loader_get_pixbuf)(loader);
+    let res = if res != ffi::FALSE && !pixbuf.is_null() {
+        (gtk.g_object_ref)(pixbuf.cast());
+        Ok(pixbuf)
+    } else if !err.is_null() {
+        Err(ffi::take_error(err, gtk.g_error_free))
+    } else {
+        Err(String::from("failed to decode image"))
+    };
+    (gtk.g_object_unref)(loader.cast());
+    res
+}
+
+unsafe fn set_default_icon(gtk: &Gtk) {
+    let Some(icon) = &branding().icon else { return; };
+    match load_pixbuf(gtk, icon) {
+        Ok(pixbuf) => {
+            (gtk.gtk_window_set_default_icon)(pixbuf);
+            (gtk.g_object_unref)(pixbuf.cast());
+        }
+        Err(err) => crate::log_warn!("Failed to load the application icon: {err}")
+    }
+}
+
+//The banner is scaled down to fit the width of the progress dialog
+const BANNER_MAX_WIDTH: c_int = 400;
+
+unsafe fn create_banner(gtk: &Gtk) -> Option<*mut GtkWidget> {
+    let brand = branding();
+    let banner = brand.banner.as_ref()?;
+
+    let pixbuf = match load_pixbuf(gtk, banner) {
+        Ok(pixbuf) => pixbuf,
+        Err(err) => {
+            crate::log_warn!("Failed to load the banner image: {err}");
+            return None;
+        }
+    };
+
+    let (width, height) = ((gtk.gdk_pixbuf_get_width)(pixbuf), (gtk.gdk_pixbuf_get_height)(pixbuf));
+    let pixbuf = if width > BANNER_MAX_WIDTH {
+        let scaled = (gtk.gdk_pixbuf_scale_simple)(pixbuf, BANNER_MAX_WIDTH, (height * BANNER_MAX_WIDTH / width).max(1), ffi::GDK_INTERP_BILINEAR);
+        (gtk.g_object_unref)(pixbuf.cast());
+        scaled
+    } else { pixbuf };
+
+    let image = (gtk.gtk_image_new_from_pixbuf)(pixbuf);
+    (gtk.g_object_unref)(pixbuf.cast());
+
+    //Wrap the image in an event box, as images don't draw a background of their own
+    let banner_box = (gtk.gtk_event_box_new)();
+    (gtk.gtk_container_add)(banner_box, image);
+
+    if let Some((r, g, b)) = brand.banner_color {
+        let css = to_cstring(&format!("* {{ background-color: #{r:02x}{g:02x}{b:02x}; }}"));
+        let provider = (gtk.gtk_css_provider_new)();
+        (gtk.gtk_css_provider_load_from_data)(provider, css.as_ptr(), -1, ptr::null_mut());
+        (gtk.gtk_style_context_add_provider)((gtk.gtk_widget_get_style_context)(banner_box), provider, ffi::GTK_STYLE_PROVIDER_PRIORITY_APPLICATION);
+        (gtk.g_object_unref)(provider.cast());
+    }
+
+    Some(banner_box)
+}
+
+const RESPONSE_COPY: c_int = 1;
+const RESPONSE_OPEN_LOG_FOLDER: c_int = 2;
+const RESPONSE_REPORT_ISSUE: c_int = 3;
+const RESPONSE_RETRY: c_int = 4;
+
+pub fn show_error_msgbox(report: &ErrorReport, can_retry: bool) -> Result<bool, Box<dyn Error>>{
+    match init_gtk() {
+        Ok(gtk) => unsafe { show_gtk_error_msgbox(gtk, report, can_retry) },
+        Err(err) => fallback_dialog_tool(err)?.show_error_msgbox(report, can_retry)
+    }
+}
+
+pub fn run_progress_action<T: Send>(descr: &str, action: impl FnOnce(&dyn ProgressAction) -> T + Send) -> Result<Option<T>, Box<dyn Error>> {
+    match init_gtk() {
+        Ok(gtk) => run_gtk_progress_action(gtk, descr, action),
+        Err(err) => fallback_dialog_tool(err)?.run_progress_action(descr, action)
+    }
+}
+
+//Fall back to dialogs driven through external tools if GTK isn't installed or can't be initialized (e.g. on Wayland-only systems)
+fn fallback_dialog_tool(gtk_err: String) -> Result<DialogTool, String> {
+    let Some(tool) = DialogTool::detect() else { return Err(gtk_err); };
+    crate::log_debug!("Failed to initialize GTK ({gtk_err}), falling back to {tool:?} dialogs");
+    Ok(tool)
+}
+
+unsafe fn show_gtk_error_msgbox(gtk: &Gtk, report: &ErrorReport, can_retry: bool) -> Result<bool, Box<dyn Error>>{
+    //Create the dialog box
+    let summary = to_cstring(&report.summary);
+    let dialog = ref_toplevel(gtk, (gtk.gtk_message_dialog_new)(ptr::null_mut(), ffi::GTK_DIALOG_MODAL, ffi::GTK_MESSAGE_ERROR, ffi::GTK_BUTTONS_NONE, c"%s".as_ptr(), summary.as_ptr()));
+    set_window_wmclass(gtk, dialog);
+    (gtk.gtk_window_set_title)(dialog, to_cstring(&tr!("error.dialog-title", app = UI_APP_NAME)).as_ptr());
+
+    // - details expander
+    if !report.details.is_empty() {
+        let details_view = (gtk.gtk_text_view_new)();
+        (gtk.gtk_text_view_set_editable)(details_view, ffi::FALSE);
+        (gtk.gtk_text_view_set_monospace)(details_view, ffi::TRUE);
+        (gtk.gtk_text_view_set_wrap_mode)(details_view, ffi::GTK_WRAP_WORD_CHAR);
+        (gtk.gtk_text_buffer_set_text)((gtk.gtk_text_view_get_buffer)(details_view), to_cstring(&report.details).as_ptr(), -1);
+
+        let details_scroll = (gtk.gtk_scrolled_window_new)(ptr::null_mut(), ptr::null_mut());
+        (gtk.gtk_widget_set_size_request)(details_scroll, 500, 150);
+        (gtk.gtk_container_add)(details_scroll, details_view);
+
+        let details_expander = (gtk.gtk_expander_new)(to_cstring(&tr!("report.details")).as_ptr());
+        (gtk.gtk_container_add)(details_expander, details_scroll);
+        (gtk.gtk_container_add)((gtk.gtk_message_dialog_get_message_area)(dialog), details_expander);
+    }
+
+    // - buttons
+    add_button(gtk, dialog, &tr!("report.copy"), RESPONSE_COPY);
+    if UI_ERROR_SHOW_LOG_FOLDER && report.log_path.is_some() {
+        add_button(gtk, dialog, &tr!("report.open-log-folder"), RESPONSE_OPEN_LOG_FOLDER);
+    }
+    if UI_ERROR_REPORT_URL.is_some() {
+        add_button(gtk, dialog, &tr!("report.report-issue"), RESPONSE_REPORT_ISSUE);
+    }
+    if can_retry {
+        add_button(gtk, dialog, &tr!("report.cancel"), ffi::GTK_RESPONSE_CANCEL);
+        add_button(gtk, dialog, &tr!("report.retry"), RESPONSE_RETRY);
+        (gtk.gtk_dialog_set_default_response)(dialog, RESPONSE_RETRY);
+    } else {
+        add_button(gtk, dialog, &tr!("report.close"), ffi::GTK_RESPONSE_CLOSE);
+        (gtk.gtk_dialog_set_default_response)(dialog, ffi::GTK_RESPONSE_CLOSE);
+    }
+
+    //Show the dialog box until it is closed
+    (gtk.gtk_widget_show_all)(dialog);
+    let resp = loop {
+        match (gtk.gtk_dialog_run)(dialog) {
+            RESPONSE_COPY => {
+                //Store the clipboard contents so that they survive the apphost exiting
+                let clipboard = (gtk.gtk_clipboard_get)(ffi::GDK_SELECTION_CLIPBOARD);
+                (gtk.gtk_clipboard_set_text)(clipboard, to_cstring(&report.to_string()).as_ptr(), -1);
+                (gtk.gtk_clipboard_store)(clipboard);
+            }
+            RESPONSE_OPEN_LOG_FOLDER => {
+                let log_dir = report.log_path.as_deref().and_then(|p| p.parent()).unwrap();
+                match filename_to_uri(gtk, log_dir) {
+                    Ok(uri) => show_uri(gtk, dialog, &uri),
+                    Err(err) => crate::log_warn!("Failed to open the log folder '{}': {err}", log_dir.display())
+                }
+            }
+            RESPONSE_REPORT_ISSUE => show_uri(gtk, dialog, UI_ERROR_REPORT_URL.unwrap()),
+            resp => break resp
+        }
+    };
+    destroy_toplevel(gtk, dialog);
+
+    Ok(resp == RESPONSE_RETRY)
+}
+
+unsafe fn filename_to_uri(gtk: &Gtk, path: &Path) -> Result<String, Box<dyn Error>> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let mut err: *mut GError = ptr::null_mut();
+    let uri = (gtk.g_filename_to_uri)(path.as_ptr(), ptr::null(), &mut err);
+    if uri.is_null() { return Err(ffi::take_error(err, gtk.g_error_free).into()); }
+    Ok(ffi::take_string(uri, gtk.g_free))
+}
+
+unsafe fn show_uri(gtk: &Gtk, dialog: *mut GtkWidget, uri: &str) {
+    let mut err: *mut GError = ptr::null_mut();
+    if (gtk.gtk_show_uri_on_window)(dialog, to_cstring(uri).as_ptr(), (gtk.gtk_get_current_event_time)(), &mut err) == ffi::FALSE {
+        crate::log_warn!("Failed to open '{uri}': {}", ffi::take_error(err, gtk.g_error_free));
+    }
+}
+
+unsafe fn confirm_cancel(gtk: &Gtk, dialog: *mut GtkWidget) -> bool {
+    let msg = to_cstring(&tr!("progress.cancel-confirm"));
+    let confirm_dialog = ref_toplevel(gtk, (gtk.gtk_message_dialog_new)(dialog, ffi::GTK_DIALOG_MODAL | ffi::GTK_DIALOG_DESTROY_WITH_PARENT, ffi::GTK_MESSAGE_QUESTION, ffi::GTK_BUTTONS_YES_NO, c"%s".as_ptr(), msg.as_ptr()));
+    set_window_wmclass(gtk, confirm_dialog);
+    (gtk.gtk_window_set_title)(confirm_dialog, to_cstring(UI_APP_NAME).as_ptr());
+
+    let resp = (gtk.gtk_dialog_run)(confirm_dialog);
+    destroy_toplevel(gtk, confirm_dialog);
+    resp == ffi::GTK_RESPONSE_YES
+}
+
+#[derive(Default)]
+struct ProgressState {
+    done: bool,
+    cancelled: bool,
+
+    dirty: bool,
+    text: String,
+    fract: f64,
+    indeterminate: bool
+}
+
+pub struct LinuxProgressAction<'a> {
+    state: &'a Mutex<ProgressState>
+}
+
+impl ProgressAction for LinuxProgressAction<'_> {
+    fn set_progress(&self, txt: &str, fract: f64) {
+        //Update the progress state
+        let mut state = self.state.lock().unwrap();
+        state.dirty = true;
+        state.text = String::from(txt);
+        state.fract = fract;
+        state.indeterminate = false;
+    }
+
+    fn set_indeterminate_progress(&self, txt: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.dirty = true;
+        state.text = String::from(txt);
+        state.indeterminate = true;
+    }
+
+    fn is_cancelled(&self) -> bool { self.state.lock().unwrap().cancelled }
+}
+
+//How often the progress bar is pulsed while showing indeterminate progress
+const PULSE_INTERVAL: Duration = Duration::from_millis(100);
+
+//The state referenced by the progress dialog's tick callback
+struct ProgressTickState<'a> {
+    gtk: &'a Gtk,
+    prog_state: &'a Mutex<ProgressState>,
+    progress_label: *mut GtkWidget,
+    progress_bar: *mut GtkWidget,
+    last_pulse: Cell<Option<Instant>>,
+    announcer: RefCell<ProgressAnnouncer>,
+
+    //Also publish the progress to the desktop's launcher / dock (the entry is dropped together with the tick state)
+    launcher_entry: Option<LauncherEntry>
+}
+
+unsafe extern "C" fn progress_tick(dialog: *mut GtkWidget, _frame_clock: GPointer, data: GPointer) -> GBoolean {
+    let tick_state = &*(data as *const ProgressTickState);
+    let gtk = tick_state.gtk;
+
+    //Check if the state is dirty
+    //If yes, update widgets
+    //Keep showing the cancellation message once the user cancelled the operation
+    let mut prog_state = tick_state.prog_state.lock().unwrap();
+    if prog_state.dirty && !prog_state.cancelled {
+        (gtk.gtk_label_set_text)(tick_state.progress_label, to_cstring(&prog_state.text).as_ptr());
+        if !prog_state.indeterminate {
+            (gtk.gtk_progress_bar_set_fraction)(tick_state.progress_bar, prog_state.fract);
+            tick_state.last_pulse.set(None);
+        }
+        if let Some(launcher_entry) = &tick_state.launcher_entry {
+            if prog_state.indeterminate {
+                launcher_entry.hide_progress();
+            } else {
+                launcher_entry.set_progress(prog_state.fract);
+            }
+        }
+        if tick_state.announcer.borrow_mut().should_announce(&prog_state.text, (!prog_state.indeterminate).then_some(prog_state.fract)) {
+            announce(gtk, tick_state.progress_bar, &prog_state.text);
+        }
+        prog_state.dirty = false;
+    }
+
+    //Keep the progress bar moving while the progress is indeterminate
+    if prog_state.indeterminate && !prog_state.cancelled && tick_state.last_pulse.get().is_none_or(|t| t.elapsed() >= PULSE_INTERVAL) {
+        (gtk.gtk_progress_bar_pulse)(tick_state.progress_bar);
+        tick_state.last_pulse.set(Some(Instant::now()));
+    }
+
+    //Check if the worker thread is done
+    //If yes, close the dialog
+    if prog_state.done {
+        (gtk.gtk_dialog_response)(dialog, ffi::GTK_RESPONSE_CLOSE);
+    }
+
+    ffi::G_SOURCE_CONTINUE
+}
+
+//Screen readers only support announcements starting with ATK 2.46, but progress bar changes are still picked up by them without it
+unsafe fn announce(gtk: &Gtk, widget: *mut GtkWidget, msg: &str) {
+    if let Some(atk_object_announce) = gtk.atk_object_announce {
+        atk_object_announce((gtk.gtk_widget_get_accessible)(widget), to_cstring(msg).as_ptr());
+    }
+}
+
+fn run_gtk_progress_action<T: Send>(gtk: &Gtk, descr: &str, action: impl FnOnce(&dyn ProgressAction) -> T + Send) -> Result<Option<T>, Box<dyn Error>> {
+    //Create the dialog GUI
+    let (dialog, progress_label, progress_bar, cancel_button) = unsafe {
+        let dialog = ref_toplevel(gtk, (gtk.gtk_dialog_new)());
+        set_window_wmclass(gtk, dialog);
+        (gtk.gtk_window_set_title)(dialog, to_cstring(UI_APP_NAME).as_ptr());
+        (gtk.gtk_widget_set_size_request)(dialog, 400, 0);
+        (gtk.gtk_window_set_resizable)(dialog, ffi::FALSE);
+
+        let dialog_content = (gtk.gtk_dialog_get_content_area)(dialog);
+        (gtk.gtk_box_set_spacing)(dialog_content, 10);
+        set_margin(gtk, dialog_content, 10);
+
+        // - banner
+        if let Some(banner) = create_banner(gtk) {
+            (gtk.gtk_container_add)(dialog_content, banner);
+        }
+
+        // - description label
+        let descr_label = (gtk.gtk_label_new)(to_cstring(descr).as_ptr());
+        (gtk.gtk_label_set_markup)(descr_label, to_cstring(&format!("<span size='medium'>{descr}</span>")).as_ptr());
+        (gtk.gtk_widget_set_margin_bottom)(descr_label, 5);
+        (gtk.gtk_container_add)(dialog_content, descr_label);
+
+        // - progress label
+        let progress_label_box = (gtk.gtk_box_new)(ffi::GTK_ORIENTATION_HORIZONTAL, 0);
+        let progress_label = (gtk.gtk_label_new)(ptr::null());
+        (gtk.gtk_container_add)(progress_label_box, progress_label);
+        (gtk.gtk_container_add)(dialog_content, progress_label_box);
+
+        // - progress bar
+        let progress_bar = (gtk.gtk_progress_bar_new)();
+        (gtk.gtk_progress_bar_set_pulse_step)(progress_bar, 0.05);
+        (gtk.atk_object_set_name)((gtk.gtk_widget_get_accessible)(progress_bar), to_cstring(descr).as_ptr());
+        (gtk.gtk_container_add)(dialog_content, progress_bar);
+
+        // - cancel button
+        let cancel_button = add_button(gtk, dialog, &tr!("progress.cancel"), ffi::GTK_RESPONSE_CANCEL);
+
+        (dialog, progress_label, progress_bar, cancel_button)
+    };
+
+    //Setup the progress state
+    let prog_state = &Mutex::new(ProgressState::default());
+
+    thread::scope(move |scope| {
+        //Start the worker thread
+        let work_thread: thread::ScopedJoinHandle<Option<T>> = scope.spawn(move || {
+            //Setup the poison pill which sets the done flag upon exit
+            struct PoisonPill<'a>(&'a Mutex<ProgressState>);
+            impl Drop for PoisonPill<'_> {
+                fn drop(&mut self) {
+                    self.0.lock().unwrap().done = true;
+                }
+            }
+            let _pill = PoisonPill(prog_state);
+
+            //Run the action
+            let ret = action(&LinuxProgressAction { state: prog_state });
+
+            if !prog_state.lock().unwrap().cancelled {
+                Some(ret)
+            } else {
+                None
+            }
+        });
+
+        //Show the dialog while executing callbacks from the worker thread
+        let tick_state = ProgressTickState { gtk, prog_state, progress_label, progress_bar, last_pulse: Cell::new(None), announcer: RefCell::default(), launcher_entry: LauncherEntry::for_session_bus() };
+        unsafe {
+            //The tick callback can only be invoked through the main loops of these dialogs, and is removed when the dialog is destroyed
+            //As such the tick state outlives all invocations of it
+            (gtk.gtk_widget_add_tick_callback)(dialog, progress_tick, &tick_state as *const ProgressTickState as GPointer, None);
+            (gtk.gtk_widget_show_all)(dialog);
+
+            //Make the cancel button reachable from the keyboard right away (Escape also cancels through the dialog's close signal)
+            (gtk.gtk_widget_grab_focus)(cancel_button);
+
+            loop {
+                match (gtk.gtk_dialog_run)(dialog) {
+                    //The tick callback closes the dialog once the worker thread is done
+                    ffi::GTK_RESPONSE_CLOSE => break,
+
+                    //The dialog has been destroyed from under us, so we can't show the cancellation state
+                    ffi::GTK_RESPONSE_NONE => {
+                        prog_state.lock().unwrap().cancelled = true;
+                        break;
+                    }
+
+                    //The user attempted to cancel the operation (either through the cancel button or by closing the dialog)
+                    //Ask for confirmation, then keep the dialog open until the worker thread has stopped
+                    _ => {
+                        if prog_state.lock().unwrap().cancelled || !confirm_cancel(gtk, dialog) { continue; }
+
+                        prog_state.lock().unwrap().cancelled = true;
+                        (gtk.gtk_widget_set_sensitive)(cancel_button, ffi::FALSE);
+                        (gtk.gtk_label_set_text)(progress_label, to_cstring(&tr!("progress.cancelling")).as_ptr());
+                    }
+                }
+            }
+
+            destroy_toplevel(gtk, dialog);
+        }
+        drop(tick_state);
+
+        //Wait for the worker thread to finish
+        match work_thread.join() {
+            Ok(r) => Ok(r),
+            Err(e) => std::panic::resume_unwind(e)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, ffi::{c_char, CStr}, ptr, rc::Rc, sync::mpsc, thread, time::{Duration, Instant}};
+
+    use crate::cfg::UI_ERROR_REPORT_URL;
+    use crate::tr;
+    use crate::ui::ErrorReport;
+
+    use super::ffi::{self, GBoolean, GList, GPointer, GType, Gtk, GtkWidget};
+    use super::{init_gtk, run_progress_action, show_error_msgbox, RESPONSE_RETRY};
+
+    //CI jobs which provide a GUI environment set PITON_REQUIRE_GUI_TESTS, so that GUI tests fail there instead of being skipped
+    pub(super) fn gui_tests_required() -> bool {
+        std::env::var_os("PITON_REQUIRE_GUI_TESTS").is_some_and(|val| !val.is_empty() && val != "0")
+    }
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    type Widget = *mut GtkWidget;
+
+    //GTK can only be used from the thread which initialized it, so all scenarios have to run as part of a single test
+    //Run under a virtual X server (e.g. xvfb-run) or with GDK_BACKEND=broadway on headless machines
+    #[test]
+    fn gtk_dialogs() {
+        if let Err(err) = init_gtk() {
+            assert!(!gui_tests_required(), "GTK couldn't be loaded / initialized: {err}");
+            eprintln!("Skipping GTK dialog tests, as GTK couldn't be loaded / initialized: {err}");
+            return;
+        }
+
+        progress_dialog_shows_progress();
+        progress_dialog_leaves_indeterminate_progress();
+        progress_dialog_accessibility();
+        progress_dialog_cancel(false);
+        progress_dialog_cancel(true);
+        progress_dialog_cancel_declined();
+        error_msgbox(false);
+        error_msgbox(true);
+    }
+
+    fn gtk() -> &'static Gtk { ffi::gtk().unwrap() }
+
+    //Repeatedly invokes the driver from within the dialogs' (nested) main loops until it reports that it's done
+    //Panicking inside of GTK callbacks aborts the process, so drivers record their observations instead of asserting
+    fn drive(mut driver: impl FnMut() -> bool + 'static) -> Rc<Cell<bool>> {
+        let timed_out = Rc::new(Cell::new(false));
+        let deadline = Instant::now() + TIMEOUT;
+
+        let timed_out_ref = timed_out.clone();
+        let callback: Box<dyn FnMut() -> bool> = Box::new(move || {
+            if driver() { return true; }
+
+            //Tear down all dialogs so that the test doesn't hang
+            if Instant::now() >= deadline {
+                timed_out_ref.set(true);
+                for window in visible_windows() {
+                    unsafe { (gtk().gtk_widget_destroy)(window); }
+                }
+                return true;
+            }
+
+            false
+        });
+
+        unsafe extern "C" fn on_timeout(data: GPointer) -> GBoolean {
+            let callback = data as *mut Box<dyn FnMut() -> bool>;
+            if !(*callback)() { return ffi::G_SOURCE_CONTINUE; }
+
+            drop(Box::from_raw(callback));
+            ffi::G_SOURCE_REMOVE
+        }
+        unsafe { (gtk().g_timeout_add)(10, on_timeout, Box::into_raw(Box::new(callback)) as GPointer) };
+
+        timed_out
+    }
+
+    //Takes ownership of a list of widgets
+    fn take_widget_list(list: *mut GList) -> Vec<Widget> {
+        let mut widgets = Vec::new();
+        let mut node = list;
+        while !node.is_null() {
+            unsafe {
+                widgets.push((*node).data as Widget);
+                node = (*node).next;
+            }
+        }
+        unsafe { (gtk().g_list_free)(list) };
+        widgets
+    }
+
+    fn is_a(widget: Widget, widget_type: unsafe extern "C" fn() -> GType) -> bool {
+        unsafe { (gtk().g_type_check_instance_is_a)(widget.cast(), widget_type()) != ffi::FALSE }
+    }
+
+    fn to_string(s: *const c_char) -> Option<String> {
+        (!s.is_null()).then(|| unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned())
+    }
+
+    fn string_property(obj: GPointer, name: &CStr) -> Option<String> {
+        let mut val: *mut c_char = ptr::null_mut();
+        unsafe { (gtk().g_object_get)(obj, name.as_ptr(), &mut val as *mut *mut c_char, ptr::null::<c_char>()) };
+        (!val.is_null()).then(|| unsafe { ffi::take_string(val, gtk().g_free) })
+    }
+
+    fn visible_windows() -> Vec<Widget> {
+        take_widget_list(unsafe { (gtk().gtk_window_list_toplevels)() }).into_iter().filter(|&w| unsafe { (gtk().gtk_widget_is_visible)(w) } != ffi::FALSE).collect()
+    }
+
+    fn progress_dialog() -> Option<Widget> {
+        visible_windows().into_iter().find(|&w| is_a(w, gtk().gtk_dialog_get_type) && !is_a(w, gtk().gtk_message_dialog_get_type))
+    }
+
+    fn message_dialog() -> Option<Widget> {
+        visible_windows().into_iter().find(|&w| is_a(w, gtk().gtk_message_dialog_get_type))
+    }
+
+    fn find_widgets(widget: Widget, widget_type: unsafe extern "C" fn() -> GType) -> Vec<Widget> {
+        let mut widgets: Vec<Widget> = is_a(widget, widget_type).then_some(widget).into_iter().collect();
+        if is_a(widget, gtk().gtk_container_get_type) {
+            for child in take_widget_list(unsafe { (gtk().gtk_container_get_children)(widget) }) {
+                widgets.extend(find_widgets(child, widget_type));
+            }
+        }
+        widgets
+    }
+
+    fn has_label(dialog: Widget, text: &str) -> bool {
+        find_widgets(dialog, gtk().gtk_label_get_type).into_iter().any(|l| to_string(unsafe { (gtk().gtk_label_get_text)(l) }).as_deref() == Some(text))
+    }
+
+    fn find_button(dialog: Widget, label: &str) -> Option<Widget> {
+        find_widgets(dialog, gtk().gtk_button_get_type).into_iter().find(|&b| to_string(unsafe { (gtk().gtk_button_get_label)(b) }).as_deref() == Some(label))
+    }
+
+    fn response(dialog: Widget, resp: i32) {
+        unsafe { (gtk().gtk_dialog_response)(dialog, resp) }
+    }
+
+    fn assert_dialogs_closed() {
+        unsafe {
+            while (gtk().gtk_events_pending)() != ffi::FALSE {
+                (gtk().gtk_main_iteration)();
+            }
+        }
+        assert!(visible_windows().is_empty(), "dialogs are still visible after they should have been closed");
+    }
+
+    fn progress_dialog_shows_progress() {
+        let (tx, rx) = mpsc::channel::<()>();
+
+        //Wait for the dialog to show the progress reported by the action, then let the action complete
+        let shown_progress = Rc::new(Cell::new(false));
+        let timed_out = drive({
+            let shown_progress = shown_progress.clone();
+            move || {
+                let Some(dialog) = progress_dialog() else { return false; };
+                let fraction = find_widgets(dialog, gtk().gtk_progress_bar_get_type).first().map(|&b| unsafe { (gtk().gtk_progress_bar_get_fraction)(b) });
+                if !has_label(dialog, "Descr") || !has_label(dialog, "Halfway there") || fraction != Some(0.5) { return false; }
+
+                shown_progress.set(true);
+                let _ = tx.send(());
+                true
+            }
+        });
+
+        let res = run_progress_action("Descr", move |act| {
+            act.set_progress("Halfway there", 0.5);
+            let _ = rx.recv();
+            42
+        });
+
+        assert!(!timed_out.get(), "timed out waiting for the progress dialog");
+        assert!(shown_progress.get());
+        assert_eq!(res.unwrap(), Some(42));
+        assert_dialogs_closed();
+    }
+
+    fn progress_dialog_leaves_indeterminate_progress() {
+        let (tx, rx) = mpsc::channel::<()>();
+        let (step_tx, step_rx) = mpsc::channel::<()>();
+
+        //Wait for the indeterminate progress to be shown, then check that regular progress replaces it
+        let shown_progress = Rc::new(Cell::new(false));
+        let timed_out = drive({
+            let shown_progress = shown_progress.clone();
+            let mut shown_indeterminate = false;
+            move || {
+                let Some(dialog) = progress_dialog() else { return false; };
+                if !shown_indeterminate {
+                    if !has_label(dialog, "Counting") { return false; }
+                    shown_indeterminate = true;
+                    let _ = step_tx.send(());
+                    return false;
+                }
+
+                let fraction = find_widgets(dialog, gtk().gtk_progress_bar_get_type).first().map(|&b| unsafe { (gtk().gtk_progress_bar_get_fraction)(b) });
+                if !has_label(dialog, "Three quarters") || fraction != Some(0.75) { return false; }
+
+                shown_progress.set(true);
+                let _ = tx.send(());
+                true
+            }
+        });
+
+        let res = run_progress_action("Descr", move |act| {
+            act.set_indeterminate_progress("Counting");
+            let _ = step_rx.recv();
+            act.set_progress("Three quarters", 0.75);
+            let _ = rx.recv();
+        });
+
+        assert!(!timed_out.get(), "timed out waiting for the progress dialog");
+        assert!(shown_progress.get());
+        assert_eq!(res.unwrap(), Some(()));
+        assert_dialogs_closed();
+    }
+
+    fn progress_dialog_accessibility() {
+        let (tx, rx) = mpsc::channel::<()>();
+
+        //Check that the progress bar is named after the description, and that the cancel button has the keyboard focus
+        let accessible = Rc::new(Cell::new(false));
+        let timed_out = drive({
+            let accessible = accessible.clone();
+            move || {
+                let Some(dialog) = progress_dialog() else { return false; };
+                let Some(&progress_bar) = find_widgets(dialog, gtk().gtk_progress_bar_get_type).first() else { return false; };
+                let Some(cancel_button) = find_button(dialog, &tr!("progress.cancel")) else { return false; };
+
+                let bar_name = unsafe {
+                    let name = (gtk().atk_object_get_name)((gtk().gtk_widget_get_accessible)(progress_bar));
+                    (!name.is_null()).then(|| CStr::from_ptr(name).to_string_lossy().into_owned())
+                };
+                accessible.set(bar_name.as_deref() == Some("Descr") && unsafe { (gtk().gtk_widget_is_focus)(cancel_button) } != ffi::FALSE);
+
+                let _ = tx.send(());
+                true
+            }
+        });
+
+        let res = run_progress_action("Descr", move |_| { let _ = rx.recv(); });
+
+        assert!(!timed_out.get(), "timed out waiting for the progress dialog");
+        assert!(accessible.get(), "the progress dialog isn't accessible");
+        assert_eq!(res.unwrap(), Some(()));
+        assert_dialogs_closed();
+    }
+
+    fn progress_dialog_cancel(via_close: bool) {
+        let (tx, rx) = mpsc::channel::<()>();
+
+        //Request cancellation, confirm it, then check that the dialog shows the cancellation state
+        let confirmed = Rc::new(Cell::new(false));
+        let shown_cancelling = Rc::new(Cell::new(false));
+        let timed_out = drive({
+            let (confirmed, shown_cancelling) = (confirmed.clone(), shown_cancelling.clone());
+            let mut requested = false;
+            move || {
+                if !requested {
+                    let Some(dialog) = progress_dialog() else { return false; };
+                    if via_close {
+                        unsafe { (gtk().gtk_window_close)(dialog) };
+                    } else {
+                        let Some(cancel_button) = find_button(dialog, &tr!("progress.cancel")) else { return false; };
+                        unsafe { (gtk().gtk_button_clicked)(cancel_button) };
+                    }
+                    requested = true;
+                } else if !confirmed.get() {
+                    let Some(confirm_dialog) = message_dialog() else { return false; };
+                    if string_property(confirm_dialog.cast(), c"text") != Some(tr!("progress.cancel-confirm")) { return false; }
+                    response(confirm_dialog, ffi::GTK_RESPONSE_YES);
+                    confirmed.set(true);
+                } else {
+                    let Some(dialog) = progress_dialog() else { return false; };
+                    let cancel_button = find_button(dialog, &tr!("progress.cancel"));
+                    if !has_label(dialog, &tr!("progress.cancelling")) || cancel_button.is_some_and(|b| unsafe { (gtk().gtk_widget_is_sensitive)(b) } != ffi::FALSE) { return false; }
+
+                    shown_cancelling.set(true);
+                    let _ = tx.send(());
+                    return true;
+                }
+                false
+            }
+        });
+
+        let res = run_progress_action("Descr", move |act| {
+            let deadline = Instant::now() + TIMEOUT;
+            while !act.is_cancelled() && Instant::now() < deadline {
+                act.set_progress("Working", 0.25);
+                thread::sleep(Duration::from_millis(10));
+            }
+            let _ = rx.recv();
+        });
+
+        assert!(!timed_out.get(), "timed out while cancelling the progress dialog");
+        assert!(confirmed.get() && shown_cancelling.get());
+        assert_eq!(res.unwrap(), None);
+        assert_dialogs_closed();
+    }
+
+    fn progress_dialog_cancel_declined() {
+        let (tx, rx) = mpsc::channel::<()>();
+
+        //Request cancellation, decline it, then check that the operation continues normally
+        let declined = Rc::new(Cell::new(false));
+        let timed_out = drive({
+            let declined = declined.clone();
+            let mut requested = false;
+            move || {
+                if !requested {
+                    let Some(cancel_button) = progress_dialog().and_then(|d| find_button(d, &tr!("progress.cancel"))) else { return false; };
+                    unsafe { (gtk().gtk_button_clicked)(cancel_button) };
+                    requested = true;
+                } else if !declined.get() {
+                    let Some(confirm_dialog) = message_dialog() else { return false; };
+                    response(confirm_dialog, ffi::GTK_RESPONSE_NO);
+                    declined.set(true);
+                } else {
+                    let Some(dialog) = progress_dialog() else { return false; };
+                    if !has_label(dialog, "Working") { return false; }
+
+                    let _ = tx.send(());
+                    return true;
+                }
+                false
+            }
+        });
+
+        let res = run_progress_action("Descr", move |act| {
+            act.set_progress("Working", 0.25);
+            let _ = rx.recv();
+            act.is_cancelled()
+        });
+
+        assert!(!timed_out.get(), "timed out while declining the cancellation");
+        assert!(declined.get());
+        assert_eq!(res.unwrap(), Some(false));
+        assert_dialogs_closed();
+    }
+
+    fn error_msgbox(retry: bool) {
+        let report = ErrorReport { summary: String::from("Something went wrong"), details: String::from("Error chain\nTarget: test"), log_path: None };
+
+        //Check the dialog contents, then either close it or retry
+        let shown_report = Rc::new(Cell::new(false));
+        let timed_out = drive({
+            let shown_report = shown_report.clone();
+            move || {
+                let Some(dialog) = message_dialog() else { return false; };
+
+                let summary = string_property(dialog.cast(), c"text");
+                let details = find_widgets(dialog, gtk().gtk_text_view_get_type).first().and_then(|&v| string_property(unsafe { (gtk().gtk_text_view_get_buffer)(v) }.cast(), c"text"));
+                shown_report.set(
+                    summary.as_deref() == Some("Something went wrong") &&
+                    details.is_some_and(|d| d == "Error chain\nTarget: test") &&
+                    find_button(dialog, &tr!("report.copy")).is_some() &&
+                    find_button(dialog, &tr!("report.open-log-folder")).is_none() &&
+                    find_button(dialog, &tr!("report.report-issue")).is_some() == UI_ERROR_REPORT_URL.is_some() &&
+                    find_button(dialog, &tr!("report.retry")).is_some() == retry
+                );
+
+                response(dialog, if retry { RESPONSE_RETRY } else { ffi::GTK_RESPONSE_CLOSE });
+                true
+            }
+        });
+
+        let res = show_error_msgbox(&report, retry);
+
+        assert!(!timed_out.get(), "timed out waiting for the error message box");
+        assert!(shown_report.get());
+        assert_eq!(res.unwrap(), retry);
+        assert_dialogs_closed();
+    }
+}
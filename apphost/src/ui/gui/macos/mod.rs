@@ -1,5 +1,27 @@
+use objc::{class, msg_send, sel, sel_impl, runtime::Object};
+
+use super::branding::branding;
+
 pub mod msgbox;
 pub use msgbox::*;
 
 pub mod progress_dialog;
-pub use progress_dialog::*;
\ No newline at end of file
+pub use progress_dialog::*;
+
+//Sets the application icon shown in the Dock and in alerts
+fn set_app_icon() {
+    let Some(icon) = branding().icon.as_deref() else { return; };
+    unsafe {
+        let data: *mut Object = msg_send![class!(NSData), dataWithBytes:icon.as_ptr() length:icon.len()];
+        let image: *mut Object = msg_send![class!(NSImage), alloc];
+        let image: *mut Object = msg_send![image, initWithData:data];
+        if image.is_null() {
+            crate::log_warn!("Failed to load the application icon: not a valid image");
+            return;
+        }
+
+        let app: *mut Object = msg_send![class!(NSApplication), sharedApplication];
+        let _: () = msg_send![app, setApplicationIconImage:image];
+        let _: () = msg_send![image, release];
+    }
+}
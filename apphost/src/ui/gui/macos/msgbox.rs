@@ -1,9 +1,103 @@
 use std::error::Error;
 
-use crate::cfg::UI_APP_NAME;
-use cacao::appkit::*;
+use objc::{class, msg_send, sel, sel_impl, runtime::{Object, BOOL, YES, NO}, Encode, Encoding};
 
-pub fn show_error_msgbox(error_msg: &str) -> Result<(), Box<dyn Error>>{
-    Alert::new(UI_APP_NAME, error_msg).show();
-    Ok(())
-}
\ No newline at end of file
+use crate::{cfg::{UI_APP_NAME, UI_ERROR_REPORT_URL, UI_ERROR_SHOW_LOG_FOLDER}, tr, ui::ErrorReport};
+
+type Id = *mut Object;
+
+//NSModalResponse values of the alert buttons, in the order they are added
+const RESPONSE_CLOSE_OR_RETRY: isize = 1000;
+const RESPONSE_COPY: isize = 1001;
+const RESPONSE_DETAILS: isize = 1002;
+const RESPONSE_OPEN_LOG_FOLDER: isize = 1003;
+const RESPONSE_REPORT_ISSUE: isize = 1004;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct NSRect { x: f64, y: f64, width: f64, height: f64 }
+
+unsafe impl Encode for NSRect {
+    fn encode() -> Encoding { unsafe { Encoding::from_str("{CGRect={CGPoint=dd}{CGSize=dd}}") } }
+}
+
+unsafe fn ns_string(s: &str) -> Id {
+    let ns_str: Id = msg_send![class!(NSString), alloc];
+    msg_send![ns_str, initWithBytes:s.as_ptr() length:s.len() encoding:4_usize /* NSUTF8StringEncoding */]
+}
+
+pub fn show_error_msgbox(report: &ErrorReport, can_retry: bool) -> Result<bool, Box<dyn Error>>{
+    unsafe {
+        //Ensure there is an application instance, as we might be called before the progress dialog ever ran
+        let app: Id = msg_send![class!(NSApplication), sharedApplication];
+        let _: () = msg_send![app, activateIgnoringOtherApps:YES];
+        super::set_app_icon();
+
+        //Create the alert
+        let alert: Id = msg_send![class!(NSAlert), new];
+        let _: () = msg_send![alert, setAlertStyle:2_usize /* NSAlertStyleCritical */];
+        let _: () = msg_send![alert, setMessageText:ns_string(&tr!("error.dialog-title", app = UI_APP_NAME))];
+        let _: () = msg_send![alert, setInformativeText:ns_string(&report.summary)];
+
+        // - buttons (NSAlert assigns responses in order, so all buttons must be added to keep them stable)
+        //The first button is the default one, so it becomes the retry button if the error can be retried
+        let _: Id = msg_send![alert, addButtonWithTitle:ns_string(&if can_retry { tr!("report.retry") } else { tr!("report.close") })];
+        let _: Id = msg_send![alert, addButtonWithTitle:ns_string(&tr!("report.copy"))];
+        let details_button: Id = msg_send![alert, addButtonWithTitle:ns_string(&tr!("report.details"))];
+        let log_folder_button: Id = msg_send![alert, addButtonWithTitle:ns_string(&tr!("report.open-log-folder"))];
+        let report_button: Id = msg_send![alert, addButtonWithTitle:ns_string(&tr!("report.report-issue"))];
+        let cancel_button: Id = msg_send![alert, addButtonWithTitle:ns_string(&tr!("report.cancel"))];
+        let _: () = msg_send![cancel_button, setKeyEquivalent:ns_string("\x1b")];
+
+        let _: () = msg_send![details_button, setHidden:BOOL::from(report.details.is_empty())];
+        let _: () = msg_send![log_folder_button, setHidden:BOOL::from(!UI_ERROR_SHOW_LOG_FOLDER || report.log_path.is_none())];
+        let _: () = msg_send![report_button, setHidden:BOOL::from(UI_ERROR_REPORT_URL.is_none())];
+        let _: () = msg_send![cancel_button, setHidden:BOOL::from(!can_retry)];
+
+        // - details view, which is shown as the accessory view once expanded
+        let frame = NSRect { x: 0.0, y: 0.0, width: 450.0, height: 150.0 };
+        let details_view: Id = msg_send![class!(NSTextView), alloc];
+        let details_view: Id = msg_send![details_view, initWithFrame:frame];
+        let _: () = msg_send![details_view, setString:ns_string(&report.details)];
+        let _: () = msg_send![details_view, setEditable:NO];
+
+        let details_scroll: Id = msg_send![class!(NSScrollView), alloc];
+        let details_scroll: Id = msg_send![details_scroll, initWithFrame:frame];
+        let _: () = msg_send![details_scroll, setHasVerticalScroller:YES];
+        let _: () = msg_send![details_scroll, setDocumentView:details_view];
+
+        //Run the alert until it is closed
+        let mut details_shown = false;
+        let resp = loop {
+            let resp: isize = msg_send![alert, runModal];
+            match resp {
+                RESPONSE_COPY => {
+                    let pasteboard: Id = msg_send![class!(NSPasteboard), generalPasteboard];
+                    let _: isize = msg_send![pasteboard, clearContents];
+                    let _: BOOL = msg_send![pasteboard, setString:ns_string(&report.to_string()) forType:ns_string("public.utf8-plain-text")];
+                }
+                RESPONSE_DETAILS => {
+                    details_shown = !details_shown;
+                    let accessory_view: Id = if details_shown { details_scroll } else { std::ptr::null_mut() };
+                    let _: () = msg_send![alert, setAccessoryView:accessory_view];
+                    let _: () = msg_send![alert, layout];
+                }
+                RESPONSE_OPEN_LOG_FOLDER => {
+                    //Reveal the log file in Finder
+                    let log_path = report.log_path.as_deref().unwrap();
+                    let log_url: Id = msg_send![class!(NSURL), fileURLWithPath:ns_string(&log_path.to_string_lossy())];
+                    let log_urls: Id = msg_send![class!(NSArray), arrayWithObject:log_url];
+                    let workspace: Id = msg_send![class!(NSWorkspace), sharedWorkspace];
+                    let _: () = msg_send![workspace, activateFileViewerSelectingURLs:log_urls];
+                }
+                RESPONSE_REPORT_ISSUE => {
+                    let url: Id = msg_send![class!(NSURL), URLWithString:ns_string(UI_ERROR_REPORT_URL.unwrap())];
+                    let workspace: Id = msg_send![class!(NSWorkspace), sharedWorkspace];
+                    let _: BOOL = msg_send![workspace, openURL:url];
+                }
+                resp => break resp
+            }
+        };
+        Ok(can_retry && resp == RESPONSE_CLOSE_OR_RETRY)
+    }
+}
@@ -1,17 +1,23 @@
+use std::cell::{Cell, RefCell};
 use std::error::Error;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use cacao::appkit::{App, AppDelegate};
 use cacao::appkit::menu::{Menu, MenuItem};
 use cacao::appkit::window::{Window, WindowConfig, WindowDelegate};
+use cacao::button::Button;
+use cacao::color::Color;
+use cacao::image::{Image, ImageView};
 use cacao::layout::{Layout, LayoutConstraint};
 use cacao::notification_center::Dispatcher;
 use cacao::progress::ProgressIndicator;
 use cacao::text::{Label, TextAlign};
 use cacao::utils::activate_cocoa_multithreading;
 use cacao::view::View;
-use crate::ui::ProgressAction;
+use objc::{class, msg_send, sel, sel_impl, runtime::Object};
+use crate::ui::{gui::{accessibility::ProgressAnnouncer, branding::branding}, ProgressAction};
 use crate::cfg::UI_APP_NAME;
+use crate::tr;
 
 #[derive(Default)]
 struct ProgressState {
@@ -21,7 +27,8 @@ struct ProgressState {
 
     dirty: bool,
     text: String,
-    fract: f64
+    fract: f64,
+    indeterminate: bool
 }
 
 pub struct MacOSProgressAction<'a> {
@@ -35,15 +42,29 @@ impl ProgressAction for MacOSProgressAction<'_> {
         state.dirty = true;
         state.text = String::from(txt);
         state.fract = fract;
+        state.indeterminate = false;
+        MacOSProgressAction::dispatch_update(&mut state);
+    }
+
+    fn set_indeterminate_progress(&self, txt: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.dirty = true;
+        state.text = String::from(txt);
+        state.indeterminate = true;
+        MacOSProgressAction::dispatch_update(&mut state);
+    }
 
+    fn is_cancelled(&self) -> bool { self.state.lock().unwrap().cancelled }
+}
+
+impl MacOSProgressAction<'_> {
+    fn dispatch_update(state: &mut ProgressState) {
         //Send a message to the main thread to update the progress window (if there isn't already a pending message)
         if !state.has_pending_msg {
-            App::<ProgressDialogApp, _>::dispatch_main(UpdateProgressMsg);
+            App::<ProgressDialogApp, _>::dispatch_main(ProgressDialogMsg::UpdateProgress);
             state.has_pending_msg = true;
         }
     }
-
-    fn is_cancelled(&self) -> bool { self.state.lock().unwrap().cancelled }
 }
 
 pub fn run_progress_action<T: Send>(descr: &str, action: impl FnOnce(&MacOSProgressAction) -> T + Send) -> Result<Option<T>, Box<dyn Error>> {
@@ -53,12 +74,17 @@ pub fn run_progress_action<T: Send>(descr: &str, action: impl FnOnce(&MacOSProgr
     //Create the application & window
     let window_delegate = ProgressDialogWindow {
         content: View::new(),
+        banner: branding().banner.as_deref().map(|data| (View::new(), ImageView::new(), Image::with_data(data))),
 
         descr_text: String::from(descr),
         descr_label: Label::new(),
 
         progress_label: Label::new(),
-        progress_bar: ProgressIndicator::new()
+        progress_bar: ProgressIndicator::new(),
+        indeterminate: Cell::new(false),
+
+        cancel_button: Button::new(&tr!("progress.cancel")),
+        announcer: RefCell::new(ProgressAnnouncer::default())
     };
 
     let app = App::new("io.github.everestapi.piton", ProgressDialogApp {
@@ -79,7 +105,7 @@ pub fn run_progress_action<T: Send>(descr: &str, action: impl FnOnce(&MacOSProgr
                     fn drop(&mut self) {
                         self.0.lock().unwrap().done = true;
                         self.0.lock().unwrap().has_pending_msg = true;
-                        App::<ProgressDialogApp, _>::dispatch_main(UpdateProgressMsg);
+                        App::<ProgressDialogApp, _>::dispatch_main(ProgressDialogMsg::UpdateProgress);
                     }
                 }
                 let _pill = PoisonPill(&prog_state);
@@ -122,6 +148,7 @@ impl AppDelegate for ProgressDialogApp {
         ]);
 
         //Bring the app to the foreground and show its window
+        super::set_app_icon();
         App::activate();
         self.window.show();
     }
@@ -129,11 +156,21 @@ impl AppDelegate for ProgressDialogApp {
     fn should_terminate_after_last_window_closed(&self) -> bool { true }
 }
 
-struct UpdateProgressMsg;
+enum ProgressDialogMsg {
+    UpdateProgress,
+    Cancel
+}
+
 impl Dispatcher for ProgressDialogApp {
-    type Message = UpdateProgressMsg;
+    type Message = ProgressDialogMsg;
+
+    fn on_ui_message(&self, msg: Self::Message) {
+        //Closing the window quits the app, which cancels the action
+        if let ProgressDialogMsg::Cancel = msg {
+            self.window.close();
+            return;
+        }
 
-    fn on_ui_message(&self, _: Self::Message) {
         //Lock the progress state
         let mut state = self.state.lock().unwrap();
         assert!(state.has_pending_msg);
@@ -147,7 +184,24 @@ impl Dispatcher for ProgressDialogApp {
         //Apply the state to the window
         let window = self.window.delegate.as_ref().unwrap();
         window.progress_label.set_text(&state.text);
-        window.progress_bar.set_value(state.fract * 100.);
+        if state.indeterminate != window.indeterminate.get() {
+            //Indeterminate progress indicators only animate while explicitly started
+            window.progress_bar.set_indeterminate(state.indeterminate);
+            if state.indeterminate {
+                window.progress_bar.start_animation();
+            } else {
+                window.progress_bar.stop_animation();
+            }
+            window.indeterminate.set(state.indeterminate);
+        }
+        if !state.indeterminate {
+            window.progress_bar.set_value(state.fract * 100.);
+        }
+
+        //Announce noteworthy progress changes to VoiceOver
+        if window.announcer.borrow_mut().should_announce(&state.text, (!state.indeterminate).then_some(state.fract)) {
+            announce(&state.text);
+        }
 
         state.has_pending_msg = false;
     }
@@ -155,12 +209,17 @@ impl Dispatcher for ProgressDialogApp {
 
 struct ProgressDialogWindow {
     content: View,
+    banner: Option<(View, ImageView, Image)>,
 
     descr_label: Label,
     descr_text: String,
 
     progress_label: Label,
     progress_bar: ProgressIndicator,
+    indeterminate: Cell<bool>,
+
+    cancel_button: Button,
+    announcer: RefCell<ProgressAnnouncer>
 }
 
 //Implementation of NSWindowDelegate
@@ -168,6 +227,28 @@ impl WindowDelegate for ProgressDialogWindow {
     const NAME: &'static str = "WindowDelegate";
 
     fn did_load(&mut self, window: Window) {
+        // - banner
+        //The banner spans the entire width of the window, and is scaled down to fit it
+        if let Some((banner_box, banner_view, banner_image)) = &self.banner {
+            if let Some((r, g, b)) = branding().banner_color {
+                banner_box.set_background_color(Color::rgb(r, g, b));
+            }
+            banner_view.set_image(banner_image);
+            banner_box.add_subview(banner_view);
+            self.content.add_subview(banner_box);
+
+            LayoutConstraint::activate(&[
+                banner_box.top.constraint_equal_to(&self.content.top),
+                banner_box.leading.constraint_equal_to(&self.content.leading),
+                banner_box.trailing.constraint_equal_to(&self.content.trailing),
+
+                banner_view.top.constraint_equal_to(&banner_box.top),
+                banner_view.bottom.constraint_equal_to(&banner_box.bottom),
+                banner_view.center_x.constraint_equal_to(&banner_box.center_x),
+                banner_view.width.constraint_less_than_or_equal_to(&banner_box.width)
+            ]);
+        }
+
         // - description label
         self.descr_label.set_text(&self.descr_text);
         self.descr_label.set_text_alignment(TextAlign::Center);
@@ -181,17 +262,27 @@ impl WindowDelegate for ProgressDialogWindow {
         // - progress bar
         self.progress_bar.set_indeterminate(false);
         self.progress_bar.set_value(0.);
+        self.progress_bar.objc.with_mut(|obj| set_accessibility_label(obj, &self.descr_text));
         self.content.add_subview(&self.progress_bar);
 
+        // - cancel button
+        //Escape triggers the button through its key equivalent
+        self.cancel_button.set_key_equivalent("\u{1b}");
+        self.cancel_button.set_action(|_| App::<ProgressDialogApp, _>::dispatch_main(ProgressDialogMsg::Cancel));
+        self.content.add_subview(&self.cancel_button);
+
         // - window configuration
         window.set_title(UI_APP_NAME);
-        window.set_minimum_content_size(400., 100.);
-        window.set_content_size(400., 100.);
+        window.set_minimum_content_size(400., 130.);
+        window.set_content_size(400., 130.);
         window.set_content_view(&self.content);
 
         // - layout constraints
         LayoutConstraint::activate(&[
-            self.descr_label.top.constraint_equal_to(&self.content.safe_layout_guide.top),
+            match &self.banner {
+                Some((banner_box, _, _)) => self.descr_label.top.constraint_equal_to(&banner_box.bottom).offset(10.),
+                None => self.descr_label.top.constraint_equal_to(&self.content.safe_layout_guide.top)
+            },
             self.descr_label.center_x.constraint_equal_to(&self.content.safe_layout_guide.center_x),
 
             self.progress_label.top.constraint_equal_to(&self.descr_label.bottom).offset(10.),
@@ -200,7 +291,48 @@ impl WindowDelegate for ProgressDialogWindow {
             self.progress_bar.top.constraint_equal_to(&self.progress_label.bottom).offset(10.),
             self.progress_bar.leading.constraint_equal_to(&self.content.safe_layout_guide.leading),
             self.progress_bar.trailing.constraint_equal_to(&self.content.safe_layout_guide.trailing),
-            self.progress_bar.bottom.constraint_equal_to(&self.content.safe_layout_guide.bottom),
+
+            self.cancel_button.top.constraint_equal_to(&self.progress_bar.bottom).offset(10.),
+            self.cancel_button.trailing.constraint_equal_to(&self.content.safe_layout_guide.trailing),
+            self.cancel_button.bottom.constraint_equal_to(&self.content.safe_layout_guide.bottom),
         ]);
     }
+}
+
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {
+    static NSAccessibilityAnnouncementRequestedNotification: *mut Object;
+    static NSAccessibilityAnnouncementKey: *mut Object;
+    static NSAccessibilityPriorityKey: *mut Object;
+
+    fn NSAccessibilityPostNotificationWithUserInfo(element: *mut Object, notification: *mut Object, user_info: *mut Object);
+}
+
+const NS_ACCESSIBILITY_PRIORITY_MEDIUM: isize = 50;
+const NS_UTF8_STRING_ENCODING: usize = 4;
+
+//The returned string is autoreleased
+unsafe fn ns_string(s: &str) -> *mut Object {
+    let string: *mut Object = msg_send![class!(NSString), alloc];
+    let string: *mut Object = msg_send![string, initWithBytes:s.as_ptr() length:s.len() encoding:NS_UTF8_STRING_ENCODING];
+    msg_send![string, autorelease]
+}
+
+fn set_accessibility_label(obj: &mut Object, label: &str) {
+    unsafe {
+        let _: () = msg_send![obj, setAccessibilityLabel:ns_string(label)];
+    }
+}
+
+//Asks VoiceOver to read out the given message without moving its focus
+fn announce(msg: &str) {
+    unsafe {
+        let priority: *mut Object = msg_send![class!(NSNumber), numberWithInteger:NS_ACCESSIBILITY_PRIORITY_MEDIUM];
+        let keys = [NSAccessibilityAnnouncementKey, NSAccessibilityPriorityKey];
+        let values = [ns_string(msg), priority];
+        let user_info: *mut Object = msg_send![class!(NSDictionary), dictionaryWithObjects:values.as_ptr() forKeys:keys.as_ptr() count:keys.len()];
+
+        let app: *mut Object = msg_send![class!(NSApplication), sharedApplication];
+        NSAccessibilityPostNotificationWithUserInfo(app, NSAccessibilityAnnouncementRequestedNotification, user_info);
+    }
 }
\ No newline at end of file
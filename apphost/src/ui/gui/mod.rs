@@ -1,6 +1,12 @@
 #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
 compile_error!("no GUI support for target OS");
 
+pub mod accessibility;
+pub mod branding;
+
+//The GUI driver is only conditionally supported on Linux, as GTK is loaded at runtime
+#[cfg(not(target_os = "linux"))] pub fn is_supported() -> bool { true }
+
 #[cfg(target_os = "linux")] pub mod linux;
 #[cfg(target_os = "linux")] pub use linux::*;
 
@@ -0,0 +1,62 @@
+use windows::{Win32::{Foundation::HWND, System::{Com::{CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED}, Variant::{VARIANT, VT_I4}}, UI::{Accessibility::{IAccPropServices, NotifyWinEvent, CLSID_AccPropServices, LiveSetting_Property_GUID, Polite, PROPID_ACC_NAME}, WindowsAndMessaging::{CHILDID_SELF, EVENT_OBJECT_LIVEREGIONCHANGED, OBJID_CLIENT}}}, core::{GUID, HSTRING}};
+
+use super::WinError;
+
+//Annotates dialog controls with additional accessibility properties (through the Dynamic Annotation API)
+//The annotations should be cleared before the controls are destroyed
+pub struct AccessibleProps {
+    services: IAccPropServices,
+    annotated: Vec<(HWND, GUID)>,
+    _com_guard: ComGuard //Dropped after the services object
+}
+
+impl AccessibleProps {
+    pub fn create() -> Result<AccessibleProps, WinError> {
+        //COM might already have been initialized differently on this thread, which is fine for our purposes
+        let com_guard = ComGuard(unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) }.is_ok());
+        let services = unsafe { CoCreateInstance(&CLSID_AccPropServices, None, CLSCTX_INPROC_SERVER)? };
+        Ok(AccessibleProps { services, annotated: Vec::new(), _com_guard: com_guard })
+    }
+
+    //Overrides the name screen readers use for the control, which is otherwise derived from its text or the preceding label
+    pub fn set_name(&mut self, control: HWND, name: &str) -> Result<(), WinError> {
+        unsafe { self.services.SetHwndPropStr(control, OBJID_CLIENT.0 as u32, CHILDID_SELF, PROPID_ACC_NAME, &HSTRING::from(name))?; }
+        self.annotated.push((control, PROPID_ACC_NAME));
+        Ok(())
+    }
+
+    //Marks the control as a (polite) live region, whose changes are announced through announce_live_region
+    pub fn set_live_region(&mut self, control: HWND) -> Result<(), WinError> {
+        unsafe {
+            let mut live_setting = VARIANT::default();
+            (*live_setting.Anonymous.Anonymous).vt = VT_I4;
+            (*live_setting.Anonymous.Anonymous).Anonymous.lVal = Polite.0;
+            self.services.SetHwndProp(control, OBJID_CLIENT.0 as u32, CHILDID_SELF, LiveSetting_Property_GUID, live_setting)?;
+        }
+        self.annotated.push((control, LiveSetting_Property_GUID));
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        for (control, prop) in self.annotated.drain(..) {
+            //Failures only leak the annotation, so there's nothing to be done about them
+            let _ = unsafe { self.services.ClearHwndProps(control, OBJID_CLIENT.0 as u32, CHILDID_SELF, &[prop]) };
+        }
+    }
+}
+
+impl Drop for AccessibleProps {
+    fn drop(&mut self) { self.clear(); }
+}
+
+struct ComGuard(bool);
+impl Drop for ComGuard {
+    fn drop(&mut self) {
+        if self.0 { unsafe { CoUninitialize(); } }
+    }
+}
+
+//Makes screen readers announce the current text of a live region control
+pub fn announce_live_region(control: HWND) {
+    unsafe { NotifyWinEvent(EVENT_OBJECT_LIVEREGIONCHANGED, control, OBJID_CLIENT.0, CHILDID_SELF as i32); }
+}
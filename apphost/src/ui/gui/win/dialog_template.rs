@@ -63,8 +63,47 @@ pub struct DialogFont<'a> {
     pub point_size: u16
 }
 
-pub fn build_dialog_template<'a>(buf: &'a mut [u8], class: WindowClass, title: &str, style: u32, ext_style: u32, size: (i16, i16), font: Option<DialogFont>, controls: &[DialogControl]) -> &'a DLGTEMPLATE {
-    let mut buf_ptr: *mut u8 = buf.as_mut_ptr();
+pub struct DialogTemplate(Vec<u32>);
+
+impl DialogTemplate {
+    pub fn template(&self) -> &DLGTEMPLATE {
+        unsafe { &*self.0.as_ptr().cast::<DLGTEMPLATE>() }
+    }
+}
+
+fn dialog_template_size(class: &WindowClass, title: &str, font: Option<&DialogFont>, controls: &[DialogControl]) -> usize {
+    //Strings are stored as null-terminated UTF-16
+    let str_size = |str: &str| (str.encode_utf16().count() + 1) * size_of::<u16>();
+    let class_size = |class: &WindowClass| match class {
+        WindowClass::None => size_of::<u16>(),
+        WindowClass::Atom(_) => 2 * size_of::<u16>(),
+        WindowClass::String(class) => str_size(class)
+    };
+
+    //Every entry can require at most 3 bytes of padding to reach DWORD alignment
+    let mut size = 3 + size_of::<DLGTEMPLATE>() + size_of::<u16>() + class_size(class) + str_size(title);
+    if let Some(font) = font {
+        size += size_of::<u16>() + str_size(&font.typeface);
+    }
+
+    for control in controls {
+        size += 3 + size_of::<DLGITEMTEMPLATE>() + class_size(&control.class);
+        size += match &control.title {
+            DialogControlTitle::None => size_of::<u16>(),
+            DialogControlTitle::Resource(_) => 2 * size_of::<u16>(),
+            DialogControlTitle::Text(txt) => str_size(txt)
+        };
+        size += size_of::<u16>() + control.creation_data.map_or(0, |data| 1 + data.len());
+    }
+
+    size
+}
+
+pub fn build_dialog_template(class: WindowClass, title: &str, style: u32, ext_style: u32, size: (i16, i16), font: Option<DialogFont>, controls: &[DialogControl]) -> DialogTemplate {
+    //Allocate a DWORD aligned buffer which is large enough to hold the entire template
+    let mut buf = vec![0_u32; dialog_template_size(&class, title, font.as_ref(), controls).div_ceil(size_of::<u32>())];
+    let buf_range = buf.as_mut_ptr_range();
+    let (mut buf_ptr, buf_end) = (buf_range.start.cast::<u8>(), buf_range.end.cast::<u8>());
 
     macro_rules! append_to_template {
         ($type: ty) => { append_to_template!($type, align_of::<$type>()) };
@@ -81,11 +120,11 @@ pub fn build_dialog_template<'a>(buf: &'a mut [u8], class: WindowClass, title: &
                 buf_ptr = buf_ptr.add(size_of::<$type>() * $count);
 
                 //Ensure we didn't overflow
-                if !buf.as_mut_ptr_range().contains(&buf_ptr) {
+                if buf_ptr > buf_end {
                     panic!("ran out of buffer space while building Win32 dialog template")
                 }
 
-                std::slice::from_raw_parts_mut::<'a>(entry_ptr, $count)
+                std::slice::from_raw_parts_mut(entry_ptr, $count)
             }
         };
     }
@@ -108,8 +147,7 @@ pub fn build_dialog_template<'a>(buf: &'a mut [u8], class: WindowClass, title: &
         panic!("DS_SETFONT flag set without specifiying a dialog font");
     }
 
-    let template = append_to_template!(DLGTEMPLATE, 4);
-    *template = DLGTEMPLATE{
+    *append_to_template!(DLGTEMPLATE, 4) = DLGTEMPLATE{
         style: style,
         dwExtendedStyle: ext_style,
         cdit: u16::try_from(controls.len()).expect("too many dialog template controls"),
@@ -184,5 +222,37 @@ pub fn build_dialog_template<'a>(buf: &'a mut [u8], class: WindowClass, title: &
         }
     }
 
-    template
+    DialogTemplate(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{build_dialog_template, DialogControl, DialogControlTitle, DialogFont, WindowClass};
+
+    #[test]
+    fn builds_templates_with_long_texts() {
+        let descr = "Setting up a runtime with a rather long description, please wait... ".repeat(50);
+        let controls = [
+            DialogControl{
+                id: 101,
+                class: WindowClass::Atom(0x0082), //static
+                style: 0, ext_style: 0, pos: (0, 0), size: (0, 0),
+                title: DialogControlTitle::Text(Cow::Borrowed(&descr)),
+                creation_data: Some(&[1, 2, 3])
+            },
+            DialogControl{
+                id: 102,
+                class: WindowClass::from("msctls_progress32"),
+                style: 0, ext_style: 0, pos: (0, 0), size: (0, 0),
+                title: DialogControlTitle::None,
+                creation_data: None
+            }
+        ];
+
+        let font = DialogFont { typeface: Cow::Borrowed("Segoe UI"), point_size: 9 };
+        let template = build_dialog_template(WindowClass::None, &"Long Application Name ".repeat(20), 0, 0, (0, 0), Some(font), &controls);
+        assert_eq!(template.template().cdit, 2);
+    }
 }
\ No newline at end of file
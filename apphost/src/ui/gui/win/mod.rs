@@ -1,5 +1,6 @@
 type WinError = windows::core::Error;
 
+mod accessibility;
 mod layout;
 mod dialog_template;
 mod dpi;
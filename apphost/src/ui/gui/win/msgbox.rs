@@ -1,16 +1,113 @@
-use std::error::Error;
-
-use windows::{Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK, MB_SYSTEMMODAL}, core::HSTRING};
-
-use crate::cfg::UI_APP_NAME;
-
-use super::WinError;
-
-pub fn show_error_msgbox(error_msg: &str) -> Result<(), Box<dyn Error>>{
-    unsafe {
-        match MessageBoxW(None, &HSTRING::from(error_msg), &HSTRING::from(UI_APP_NAME), MB_OK | MB_ICONERROR | MB_SYSTEMMODAL).0 {
-            0 => Err(Box::new(WinError::from_win32())),
-            _ => Ok(())
-        }
-    }
-}
\ No newline at end of file
+use std::{error::Error, mem, ptr};
+
+use windows::{Win32::{UI::{Controls::{TaskDialogIndirect, TASKDIALOGCONFIG, TASKDIALOG_BUTTON, TASKDIALOG_FLAGS, TDF_ALLOW_DIALOG_CANCELLATION, TDF_ENABLE_HYPERLINKS, TDF_SIZE_TO_CONTENT, TDCBF_CLOSE_BUTTON, TDCBF_CANCEL_BUTTON, TD_ERROR_ICON, TDN_BUTTON_CLICKED, TDN_HYPERLINK_CLICKED}, Shell::ShellExecuteW, WindowsAndMessaging::SW_SHOWNORMAL}, System::{DataExchange::{OpenClipboard, EmptyClipboard, SetClipboardData, CloseClipboard}, Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE}}, Foundation::{HWND, WPARAM, LPARAM, HANDLE, GlobalFree, S_OK, S_FALSE}}, core::{HSTRING, PCWSTR, HRESULT}};
+
+use crate::{cfg::{UI_APP_NAME, UI_ERROR_REPORT_URL, UI_ERROR_SHOW_LOG_FOLDER}, tr, ui::ErrorReport};
+
+use super::WinError;
+
+const IDB_COPY: i32 = 100;
+const IDB_OPEN_LOG_FOLDER: i32 = 101;
+const IDB_RETRY: i32 = 102;
+
+//The Ole feature of the windows crate is too heavy to pull in just for this constant
+const CF_UNICODETEXT: u32 = 13;
+
+pub fn show_error_msgbox(report: &ErrorReport, can_retry: bool) -> Result<bool, Box<dyn Error>>{
+    let title = HSTRING::from(tr!("error.dialog-title", app = UI_APP_NAME));
+    let summary = HSTRING::from(report.summary.as_str());
+    let details = HSTRING::from(report.details.as_str());
+    let details_label = HSTRING::from(tr!("report.details"));
+
+    //Setup the custom buttons
+    let copy_label = HSTRING::from(tr!("report.copy"));
+    let open_log_label = HSTRING::from(tr!("report.open-log-folder"));
+    let retry_label = HSTRING::from(tr!("report.retry"));
+
+    let mut buttons = vec![TASKDIALOG_BUTTON { nButtonID: IDB_COPY, pszButtonText: PCWSTR(copy_label.as_ptr()) }];
+    if UI_ERROR_SHOW_LOG_FOLDER && report.log_path.is_some() {
+        buttons.push(TASKDIALOG_BUTTON { nButtonID: IDB_OPEN_LOG_FOLDER, pszButtonText: PCWSTR(open_log_label.as_ptr()) });
+    }
+    if can_retry {
+        buttons.push(TASKDIALOG_BUTTON { nButtonID: IDB_RETRY, pszButtonText: PCWSTR(retry_label.as_ptr()) });
+    }
+
+    //The issue report link is shown in the footer
+    let footer = HSTRING::from(UI_ERROR_REPORT_URL.map(|url| format!("<a href=\"{url}\">{}</a>", tr!("report.report-issue"))).unwrap_or_default());
+
+    let mut config = TASKDIALOGCONFIG {
+        cbSize: mem::size_of::<TASKDIALOGCONFIG>() as u32,
+        dwFlags: TASKDIALOG_FLAGS(TDF_ALLOW_DIALOG_CANCELLATION.0 | TDF_ENABLE_HYPERLINKS.0 | TDF_SIZE_TO_CONTENT.0),
+        dwCommonButtons: if can_retry { TDCBF_CANCEL_BUTTON } else { TDCBF_CLOSE_BUTTON },
+        pszWindowTitle: PCWSTR(title.as_ptr()),
+        pszContent: PCWSTR(summary.as_ptr()),
+        cButtons: buttons.len() as u32,
+        pButtons: buttons.as_ptr(),
+        nDefaultButton: if can_retry { IDB_RETRY } else { 0 },
+        pfCallback: Some(error_dialog_callback),
+        lpCallbackData: report as *const ErrorReport as isize,
+        ..Default::default()
+    };
+    config.Anonymous1.pszMainIcon = TD_ERROR_ICON;
+    if !report.details.is_empty() {
+        config.pszExpandedInformation = PCWSTR(details.as_ptr());
+        config.pszCollapsedControlText = PCWSTR(details_label.as_ptr());
+    }
+    if !footer.is_empty() {
+        config.pszFooter = PCWSTR(footer.as_ptr());
+    }
+
+    let mut clicked_button = 0;
+    unsafe {
+        TaskDialogIndirect(&config, Some(&mut clicked_button), None, None)?;
+    }
+    Ok(clicked_button == IDB_RETRY)
+}
+
+unsafe extern "system" fn error_dialog_callback(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM, ref_data: isize) -> HRESULT {
+    let report = &*(ref_data as *const ErrorReport);
+
+    match msg as i32 {
+        //Keep the dialog open after handling our custom buttons
+        m if m == TDN_BUTTON_CLICKED.0 => match wparam.0 as i32 {
+            IDB_COPY => {
+                if let Err(err) = copy_to_clipboard(hwnd, &report.to_string()) {
+                    crate::log_warn!("Failed to copy the error report to the clipboard: {err}");
+                }
+                S_FALSE
+            }
+            IDB_OPEN_LOG_FOLDER => {
+                if let Some(log_dir) = report.log_path.as_deref().and_then(|p| p.parent()) {
+                    ShellExecuteW(hwnd, &HSTRING::from("open"), &HSTRING::from(log_dir.as_os_str()), PCWSTR::null(), PCWSTR::null(), SW_SHOWNORMAL);
+                }
+                S_FALSE
+            }
+            _ => S_OK
+        },
+
+        m if m == TDN_HYPERLINK_CLICKED.0 => {
+            ShellExecuteW(hwnd, &HSTRING::from("open"), PCWSTR(lparam.0 as *const u16), PCWSTR::null(), PCWSTR::null(), SW_SHOWNORMAL);
+            S_OK
+        }
+
+        _ => S_OK
+    }
+}
+
+unsafe fn copy_to_clipboard(hwnd: HWND, text: &str) -> Result<(), WinError> {
+    //Allocate a global memory block holding the null-terminated text
+    let text = text.replace('\n', "\r\n").encode_utf16().chain([0]).collect::<Vec<u16>>();
+    let hmem = GlobalAlloc(GMEM_MOVEABLE, text.len() * mem::size_of::<u16>())?;
+    ptr::copy_nonoverlapping(text.as_ptr(), GlobalLock(hmem) as *mut u16, text.len());
+    let _ = GlobalUnlock(hmem);
+
+    //Hand the memory block over to the clipboard
+    OpenClipboard(hwnd)?;
+    let res = EmptyClipboard().and_then(|_| SetClipboardData(CF_UNICODETEXT, HANDLE(hmem.0 as isize)));
+    CloseClipboard()?;
+
+    if res.is_err() {
+        let _ = GlobalFree(hmem);
+    }
+    res.map(|_| ())
+}
@@ -1,14 +1,17 @@
 use std::{sync::{OnceLock, Mutex}, mem::{self}, error::Error, borrow::Cow, process::abort, ffi::c_void, thread};
 
-use windows::{Win32::{UI::{Controls::{INITCOMMONCONTROLSEX, InitCommonControlsEx, ICC_PROGRESS_CLASS, PROGRESS_CLASS, PBM_SETPOS, PBM_SETRANGE}, WindowsAndMessaging::{WS_CAPTION, WS_POPUP, WS_SYSMENU, DS_MODALFRAME, DialogBoxIndirectParamA, WS_VISIBLE, WS_CHILD, GetDialogBaseUnits, GetSystemMetrics, SM_CYVSCROLL, WM_CLOSE, EndDialog, WM_INITDIALOG, SetWindowPos, SWP_NOZORDER, GetWindowRect, GetDesktopWindow, SWP_NOSIZE, SWP_NOACTIVATE, WM_GETDPISCALEDSIZE, WM_DPICHANGED, WINDOW_LONG_PTR_INDEX, SetWindowLongPtrW, DLGPROC, NONCLIENTMETRICSW, SPI_GETNONCLIENTMETRICS, SystemParametersInfoW, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, MSG, PeekMessageW, PM_REMOVE, GetWindowLongPtrW, GetDlgItem, WS_EX_COMPOSITED, SetTimer, WM_TIMER, SetWindowTextW, SendMessageA}}, System::{LibraryLoader::GetModuleHandleA, SystemServices::{SS_LEFT, SS_CENTER}}, Foundation::{LPARAM, WPARAM, HWND, RECT, SIZE, SetLastError, ERROR_SUCCESS, GetLastError, LRESULT}, Graphics::Gdi::{GetDC, ReleaseDC, DT_CALCRECT, DT_WORDBREAK, DrawTextW, HDC, RedrawWindow, HRGN, RDW_INVALIDATE, RDW_FRAME, RDW_ERASE, HFONT, DeleteObject, LOGFONTW, CreateFontIndirectW, SelectObject, HGDIOBJ, InvalidateRect}}, core::{PCSTR, HSTRING}};
+use windows::{Win32::{UI::{Controls::{INITCOMMONCONTROLSEX, InitCommonControlsEx, ICC_PROGRESS_CLASS, PROGRESS_CLASS, PBM_SETPOS, PBM_SETRANGE, PBM_SETMARQUEE, PBS_MARQUEE}, WindowsAndMessaging::{WS_CAPTION, WS_POPUP, WS_SYSMENU, DS_MODALFRAME, DialogBoxIndirectParamA, WS_VISIBLE, WS_CHILD, GetDialogBaseUnits, GetSystemMetrics, SM_CYVSCROLL, WM_CLOSE, EndDialog, WM_INITDIALOG, SetWindowPos, SWP_NOZORDER, GetWindowRect, GetDesktopWindow, SWP_NOSIZE, SWP_NOACTIVATE, WM_GETDPISCALEDSIZE, WM_DPICHANGED, WINDOW_LONG_PTR_INDEX, SetWindowLongPtrW, DLGPROC, NONCLIENTMETRICSW, SPI_GETNONCLIENTMETRICS, SystemParametersInfoW, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, MSG, PeekMessageW, PM_REMOVE, GetWindowLongPtrW, GetDlgItem, WS_EX_COMPOSITED, SetTimer, WM_TIMER, SetWindowTextW, SendMessageA, HICON, CreateIconFromResourceEx, DestroyIcon, LR_DEFAULTCOLOR, WM_SETICON, ICON_BIG, ICON_SMALL, SM_CXICON, SM_CYICON, SM_CXSMICON, SM_CYSMICON, STM_SETIMAGE, IMAGE_ICON, WM_CTLCOLORSTATIC, GetWindowLongW, SetWindowLongW, GWL_STYLE, WS_TABSTOP, BS_DEFPUSHBUTTON, IDCANCEL, WM_COMMAND, WM_DESTROY, MessageBoxW, MB_YESNO, MB_ICONQUESTION, IDYES}, Input::KeyboardAndMouse::EnableWindow}, System::{LibraryLoader::GetModuleHandleA, SystemServices::{SS_LEFT, SS_CENTER, SS_ICON, SS_CENTERIMAGE, SS_REALSIZEIMAGE}}, Foundation::{LPARAM, WPARAM, HWND, RECT, SIZE, SetLastError, ERROR_SUCCESS, GetLastError, LRESULT, COLORREF}, Graphics::Gdi::{GetDC, ReleaseDC, DT_CALCRECT, DT_WORDBREAK, DrawTextW, HDC, RedrawWindow, HRGN, RDW_INVALIDATE, RDW_FRAME, RDW_ERASE, HFONT, DeleteObject, LOGFONTW, CreateFontIndirectW, SelectObject, HGDIOBJ, InvalidateRect, HBRUSH, CreateSolidBrush}}, core::{PCSTR, HSTRING}};
 
-use crate::{cfg::UI_APP_NAME, ui::{gui::win::{dialog_template::{build_dialog_template, DialogControl, DialogControlTitle, WindowClass}, dpi::{DPIAwarenessOverride, DPIAwarenessContext, DialogDPIChangeBehaviors}, WinError}, ProgressAction}};
+use crate::{cfg::UI_APP_NAME, tr, ui::{gui::{accessibility::ProgressAnnouncer, branding::branding, win::{accessibility::{announce_live_region, AccessibleProps}, dialog_template::{build_dialog_template, DialogControl, DialogControlTitle, WindowClass}, dpi::{DPIAwarenessOverride, DPIAwarenessContext, DialogDPIChangeBehaviors, DPI_100P}, WinError}}, ProgressAction}};
 
 use super::{layout::{ComponentLayout, WindowLayout, LayoutParams, LayoutRect}, dpi::DPIMetrics};
 
-const IDC_DESCR_LABEL: u16 = 1;
-const IDC_PROGRESS_LABEL: u16 = 2;
-const IDC_PROGRESS_BAR: u16 = 3;
+//Control IDs mustn't clash with the IDs of standard buttons, as the cancel button (which Escape triggers) has to use IDCANCEL
+const IDC_DESCR_LABEL: u16 = 101;
+const IDC_PROGRESS_LABEL: u16 = 102;
+const IDC_PROGRESS_BAR: u16 = 103;
+const IDC_BANNER: u16 = 104;
+const IDC_CANCEL_BUTTON: u16 = IDCANCEL.0 as u16;
 
 #[derive(Default)]
 struct ProgressState {
@@ -17,12 +20,14 @@ struct ProgressState {
 
     dirty: bool,
     text: String,
-    fract: f64
+    fract: f64,
+    indeterminate: bool
 }
 
 struct DialogWindow<'a> {
     state: &'a Mutex<ProgressState>,
     done_delay: i32,
+    marquee: bool,
 
     dpi_override: DPIAwarenessOverride,
     inital_dpi: (i32, i32),
@@ -32,10 +37,25 @@ struct DialogWindow<'a> {
     dialog_font: FontHolder,
     window_layout: WindowLayout,
 
+    app_icons: Option<(IconHolder, IconHolder)>,
+    banner: Option<BannerImage>,
+
+    acc_props: Option<AccessibleProps>,
+    announcer: ProgressAnnouncer,
+
     descr_text: &'a str,
     descr_label: ComponentLayout,
     progress_label: ComponentLayout,
-    progress_bar: ComponentLayout
+    progress_bar: ComponentLayout,
+    cancel_button: ComponentLayout
+}
+
+struct BannerImage {
+    data: &'static [u8],
+    size: (i32, i32),
+    layout: ComponentLayout,
+    icon: IconHolder,
+    brush: BrushHolder
 }
 
 pub struct WinProgressAction<'a> {
@@ -49,6 +69,14 @@ impl ProgressAction for WinProgressAction<'_> {
         state.dirty = true;
         state.text = String::from(txt);
         state.fract = fract;
+        state.indeterminate = false;
+    }
+
+    fn set_indeterminate_progress(&self, txt: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.dirty = true;
+        state.text = String::from(txt);
+        state.indeterminate = true;
     }
  
     fn is_cancelled(&self) -> bool { self.state.lock().unwrap().cancelled }
@@ -88,42 +116,72 @@ pub fn run_progress_action<T: Send>(descr: &str, action: impl FnOnce(&WinProgres
     }
     let dpi_override = dpi_override.expect("failed to apply DPI awareness override");
 
+    //Load the banner image
+    //Only its dimensions are determined here, as the icon is created at the size matching the dialog's DPI
+    let banner = branding().banner.as_deref().and_then(|data| match png_dimensions(data) {
+        Some(size) => Some(BannerImage { data, size, layout: ComponentLayout::default(), icon: IconHolder::default(), brush: BrushHolder::default() }),
+        None => {
+            crate::log_warn!("Failed to load the banner image: not a valid PNG file");
+            None
+        }
+    });
+
     //Build the dialog box template
-    let mut diag_template_buf = [0_u8; 1024];
+    let cancel_label = tr!("progress.cancel");
+    let mut diag_controls = Vec::with_capacity(5);
+    if banner.is_some() {
+        diag_controls.push(DialogControl{
+            id: IDC_BANNER,
+            class: WindowClass::Atom(0x0082), //static
+            style: (WS_VISIBLE | WS_CHILD).0 | SS_ICON.0 | SS_CENTERIMAGE.0 | SS_REALSIZEIMAGE.0,
+            ext_style: 0, pos: (0, 0), size: (0, 0),
+            title: DialogControlTitle::None,
+            creation_data: None
+        });
+    }
+    diag_controls.extend([
+        DialogControl{
+            id: IDC_DESCR_LABEL,
+            class: WindowClass::Atom(0x0082), //static
+            style: (WS_VISIBLE | WS_CHILD).0 | SS_CENTER.0,
+            ext_style: 0, pos: (0, 0), size: (0, 0),
+            title: DialogControlTitle::Text(Cow::Borrowed(descr)),
+            creation_data: None
+        },
+        DialogControl{
+            id: IDC_PROGRESS_LABEL,
+            class: WindowClass::Atom(0x0082), //static
+            style: (WS_VISIBLE | WS_CHILD).0 | SS_LEFT.0,
+            ext_style: 0, pos: (0, 0), size: (0, 0),
+            title: DialogControlTitle::None,
+            creation_data: None
+        },
+        DialogControl{
+            id: IDC_PROGRESS_BAR,
+            class: WindowClass::try_from(PROGRESS_CLASS)?,
+            style: (WS_VISIBLE | WS_CHILD).0,
+            ext_style: 0, pos: (0, 0), size: (0, 0),
+            title: DialogControlTitle::None,
+            creation_data: None
+        },
+        DialogControl{
+            id: IDC_CANCEL_BUTTON,
+            class: WindowClass::Atom(0x0080), //button
+            style: (WS_VISIBLE | WS_CHILD | WS_TABSTOP).0 | BS_DEFPUSHBUTTON as u32,
+            ext_style: 0, pos: (0, 0), size: (0, 0),
+            title: DialogControlTitle::Text(Cow::Borrowed(&cancel_label)),
+            creation_data: None
+        }
+    ]);
+
     let diag_template = build_dialog_template(
-        &mut diag_template_buf,
         WindowClass::None,
         UI_APP_NAME,
         (WS_POPUP | WS_CAPTION | WS_SYSMENU).0 | DS_MODALFRAME as u32,
         WS_EX_COMPOSITED.0, //Double buffered
         (0, 0),
         None,
-        &[
-            DialogControl{
-                id: IDC_DESCR_LABEL,
-                class: WindowClass::Atom(0x0082), //static
-                style: (WS_VISIBLE | WS_CHILD).0 | SS_CENTER.0,
-                ext_style: 0, pos: (0, 0), size: (0, 0),
-                title: DialogControlTitle::Text(Cow::Borrowed(descr)),
-                creation_data: None
-            },
-            DialogControl{
-                id: IDC_PROGRESS_LABEL,
-                class: WindowClass::Atom(0x0082), //static
-                style: (WS_VISIBLE | WS_CHILD).0 | SS_LEFT.0,
-                ext_style: 0, pos: (0, 0), size: (0, 0),
-                title: DialogControlTitle::None,
-                creation_data: None
-            },
-            DialogControl{
-                id: IDC_PROGRESS_BAR,
-                class: WindowClass::try_from(PROGRESS_CLASS)?,
-                style: (WS_VISIBLE | WS_CHILD).0,
-                ext_style: 0, pos: (0, 0), size: (0, 0),
-                title: DialogControlTitle::None,
-                creation_data: None
-            }
-        ]
+        &diag_controls
     );
 
     //Setup the progress state
@@ -153,10 +211,10 @@ pub fn run_progress_action<T: Send>(descr: &str, action: impl FnOnce(&WinProgres
 
         //Create and run the dialog        
         unsafe {
-            let mut diag_window: DialogWindow = DialogWindow::new(dpi_override, &prog_state, descr);
+            let mut diag_window: DialogWindow = DialogWindow::new(dpi_override, &prog_state, descr, banner);
             match DialogBoxIndirectParamA(
                 GetModuleHandleA(PCSTR::null())?,
-                diag_template,
+                diag_template.template(),
                 None,
                 Some(progress_dialog_proc),
                 LPARAM(&mut diag_window as *mut _ as isize)
@@ -230,9 +288,33 @@ extern "system" fn progress_dialog_proc(window_handle: HWND, msg: u32, _msg_para
                 diag_window.descr_label.handle = get_control_handle!(IDC_DESCR_LABEL);
                 diag_window.progress_label.handle = get_control_handle!(IDC_PROGRESS_LABEL);
                 diag_window.progress_bar.handle = get_control_handle!(IDC_PROGRESS_BAR);
+                diag_window.cancel_button.handle = get_control_handle!(IDC_CANCEL_BUTTON);
+                if let Some(banner) = &mut diag_window.banner {
+                    banner.layout.handle = get_control_handle!(IDC_BANNER);
+                }
 
                 diag_window.init().expect("failed to initialize dialog layout");
 
+                //Name the progress bar after the dialog's description, and announce changes of the progress label to screen readers
+                //Accessibility annotations are optional, so only log failures
+                match AccessibleProps::create() {
+                    Ok(mut acc_props) => {
+                        if let Err(err) = acc_props.set_name(diag_window.progress_bar.handle, diag_window.descr_text).and_then(|_| acc_props.set_live_region(diag_window.progress_label.handle)) {
+                            crate::log_debug!("Failed to annotate progress dialog controls: {err}");
+                        }
+                        diag_window.acc_props = Some(acc_props);
+                    }
+                    Err(err) => crate::log_debug!("Failed to create accessibility annotations: {err}")
+                }
+
+                //Set the window icons
+                if let Some((big_icon, small_icon)) = &diag_window.app_icons {
+                    unsafe {
+                        SendMessageA(window_handle, WM_SETICON, WPARAM(ICON_BIG as usize), LPARAM(big_icon.0.0));
+                        SendMessageA(window_handle, WM_SETICON, WPARAM(ICON_SMALL as usize), LPARAM(small_icon.0.0));
+                    }
+                }
+
                 //If we are using Per-Monitor v2 DPI awareness, disable default dialog resizing
                 if diag_window.dpi_override.get_awareness() == DPIAwarenessContext::PerMonitorAwareV2 {
                     DialogDPIChangeBehaviors::set_for(
@@ -267,17 +349,35 @@ extern "system" fn progress_dialog_proc(window_handle: HWND, msg: u32, _msg_para
 
                 //Check if the progress state was modified
                 //If yes, update controls and invalidate the window
-                if prog_state.dirty {
+                //Keep showing the cancellation message once the user cancelled the operation
+                if prog_state.dirty && !prog_state.cancelled {
                     unsafe {
                         //Update the progress label
                         SetWindowTextW(diag_window.progress_label.handle, &HSTRING::from(&prog_state.text)).expect("failed to set progress label text");
 
+                        //Announce noteworthy progress changes to screen readers
+                        if diag_window.announcer.should_announce(&prog_state.text, (!prog_state.indeterminate).then_some(prog_state.fract)) {
+                            announce_live_region(diag_window.progress_label.handle);
+                        }
+
+                        //Switch the progress bar into / out of marquee mode
+                        //The marquee style has to be set before the animation can be started
+                        if prog_state.indeterminate != diag_window.marquee {
+                            let bar_style = GetWindowLongW(diag_window.progress_bar.handle, GWL_STYLE) as u32;
+                            let bar_style = if prog_state.indeterminate { bar_style | PBS_MARQUEE } else { bar_style & !PBS_MARQUEE };
+                            SetWindowLongW(diag_window.progress_bar.handle, GWL_STYLE, bar_style as i32);
+                            SendMessageA(diag_window.progress_bar.handle, PBM_SETMARQUEE, WPARAM(prog_state.indeterminate as usize), LPARAM(30));
+                            diag_window.marquee = prog_state.indeterminate;
+                        }
+
                         //Update the progress bar
                         //Top MS design quality here: the bar will "smoothly animate" (=lag behind)
                         //To bypass this, set the position to state+1 first, then decrement to state, which is instant
                         //We have to have a special case for when we hit 100% as well, as we need to temporarily extend the range in that case
                         let val = (prog_state.fract * 100_f64) as usize;
-                        if val < 100 {
+                        if prog_state.indeterminate {
+                            //The marquee animates on its own
+                        } else if val < 100 {
                             SendMessageA(diag_window.progress_bar.handle, PBM_SETPOS, WPARAM(val+1), LPARAM::default());
                             SendMessageA(diag_window.progress_bar.handle, PBM_SETPOS, WPARAM(val), LPARAM::default());
                         } else {
@@ -299,7 +399,9 @@ extern "system" fn progress_dialog_proc(window_handle: HWND, msg: u32, _msg_para
                     //Wait a bit (0.1s) so that it looks less abrupt
                     if diag_window.done_delay >= 6 {
                         unsafe {
-                            EndDialog(window_handle, 1).expect("failed to end progress dialog");
+                            if let Err(err) = EndDialog(window_handle, 1) {
+                                crate::log_warn!("Failed to end progress dialog: {err}");
+                            }
                         }
                     } else {
                         diag_window.done_delay += 1;   
@@ -347,12 +449,35 @@ extern "system" fn progress_dialog_proc(window_handle: HWND, msg: u32, _msg_para
 
                 1
             }
-            WM_CLOSE => {
-                //End the dialog
-                unsafe {
-                    EndDialog(window_handle, 1).expect("failed to cancel progress dialog");
+            WM_CTLCOLORSTATIC => {
+                let diag_window = get_dialog_window!(window_handle);
+
+                //Paint the area behind the banner using the banner colour
+                //Dialog procedures can return the brush directly for this message
+                match &diag_window.banner {
+                    Some(banner) if banner.layout.handle.0 == _msg_param2.0 && banner.brush.has_brush() => banner.brush.0.0,
+                    _ => 0
+                }
+            }
+            WM_COMMAND => {
+                //The cancel button was pressed (or Escape was hit)
+                if (_msg_param1.0 & 0xffff) as u16 != IDC_CANCEL_BUTTON { return 0; }
+
+                get_dialog_window!(window_handle).request_cancel(window_handle);
+                1
+            }
+            WM_DESTROY => {
+                //Clear accessibility annotations while the controls still exist
+                let diag_window = get_dialog_window!(window_handle);
+                if let Some(acc_props) = &mut diag_window.acc_props {
+                    acc_props.clear();
                 }
 
+                0
+            }
+            WM_CLOSE => {
+                //Closing the dialog cancels the operation as well
+                get_dialog_window!(window_handle).request_cancel(window_handle);
                 1
             }
             _ => 0
@@ -394,10 +519,11 @@ fn center_window(window_handle: HWND) -> Result<(), WinError> {
 impl<'a> DialogWindow<'a> {
     const DIALOG_WIDTH: i32 = 180;
 
-    fn new(dpi_override: DPIAwarenessOverride, state: &'a Mutex<ProgressState>, descr_text: &'a str) -> DialogWindow<'a> {
+    fn new(dpi_override: DPIAwarenessOverride, state: &'a Mutex<ProgressState>, descr_text: &'a str, banner: Option<BannerImage>) -> DialogWindow<'a> {
         DialogWindow {
             state,
             done_delay: 0,
+            marquee: false,
 
             dpi_override,
             inital_dpi: (0, 0),
@@ -407,10 +533,34 @@ impl<'a> DialogWindow<'a> {
             dialog_font: FontHolder::default(),
             window_layout: WindowLayout::default(),
 
+            app_icons: None,
+            banner,
+
+            acc_props: None,
+            announcer: ProgressAnnouncer::default(),
+
             descr_text: descr_text,
             descr_label: ComponentLayout::default(),
             progress_label: ComponentLayout::default(),
             progress_bar: ComponentLayout::default(),
+            cancel_button: ComponentLayout::default()
+        }
+    }
+
+    //Asks the user for confirmation, then tells the worker thread to stop
+    //The dialog stays open until the worker thread reports that it is done
+    fn request_cancel(&mut self, window_handle: HWND) {
+        if self.state.lock().unwrap().cancelled { return; }
+
+        let confirm = unsafe { MessageBoxW(window_handle, &HSTRING::from(tr!("progress.cancel-confirm")), &HSTRING::from(UI_APP_NAME), MB_YESNO | MB_ICONQUESTION) };
+        if confirm != IDYES { return; }
+
+        self.state.lock().unwrap().cancelled = true;
+        unsafe {
+            EnableWindow(self.cancel_button.handle, false);
+            if let Err(err) = SetWindowTextW(self.progress_label.handle, &HSTRING::from(tr!("progress.cancelling"))) {
+                crate::log_warn!("Failed to show the cancellation message: {err}");
+            }
         }
     }
 
@@ -423,6 +573,22 @@ impl<'a> DialogWindow<'a> {
         //Query the initial DPI
         self.inital_dpi = self.dpi_override.get_current_dpi(self.window_layout.handle)?;
 
+        //Load the application icon at the sizes used by the title bar and taskbar
+        if let Some(icon) = branding().icon.as_deref() {
+            let (big_size, small_size) = unsafe {
+                ((GetSystemMetrics(SM_CXICON), GetSystemMetrics(SM_CYICON)), (GetSystemMetrics(SM_CXSMICON), GetSystemMetrics(SM_CYSMICON)))
+            };
+            match IconHolder::create_from_png(icon, big_size).and_then(|big| Ok((big, IconHolder::create_from_png(icon, small_size)?))) {
+                Ok(icons) => self.app_icons = Some(icons),
+                Err(err) => crate::log_warn!("Failed to load the application icon: {err}")
+            }
+        }
+
+        //Create the banner background brush
+        if let (Some(banner), Some((r, g, b))) = (&mut self.banner, branding().banner_color) {
+            banner.brush = BrushHolder::create_solid(COLORREF(r as u32 | (g as u32) << 8 | (b as u32) << 16))?;
+        }
+
         Ok(())
     }
 
@@ -442,6 +608,7 @@ impl<'a> DialogWindow<'a> {
     fn update_and_apply(&mut self, dpi: (i32, i32)) -> Result<(), WinError> {
         self.update_font(dpi)?;
         self.update(&self.calc_layout_params(dpi))?;
+        self.update_banner_icon(dpi)?;
         self.apply_font()?;
         self.apply(dpi)?;
         Ok(())
@@ -450,9 +617,13 @@ impl<'a> DialogWindow<'a> {
     fn apply(&self, dpi: (i32, i32)) -> Result<(), WinError> {
         //Apply layouts
         let params = &self.calc_layout_params(dpi);
+        if let Some(banner) = &self.banner {
+            banner.layout.apply(params)?;
+        }
         self.descr_label.apply(params)?;
         self.progress_label.apply(params)?;
         self.progress_bar.apply(params)?;
+        self.cancel_button.apply(params)?;
         self.window_layout.apply(params)?;
 
         Ok(())
@@ -462,13 +633,26 @@ impl<'a> DialogWindow<'a> {
         //Update control fonts
         self.descr_label.set_font(self.dialog_font.0)?;
         self.progress_label.set_font(self.dialog_font.0)?;
+        self.cancel_button.set_font(self.dialog_font.0)?;
         Ok(())
     }
 
     fn update(&mut self, params: &LayoutParams) -> Result<(), WinError> {
+        //Update the banner layout
+        //The banner spans the entire width of the dialog, without any margins
+        let mut content_top = 0;
+        if let Some(banner) = &mut self.banner {
+            let (_, banner_height) = DialogWindow::banner_pixel_size(banner, params);
+            banner.layout.x = 0;
+            banner.layout.y = 0;
+            banner.layout.width = DialogWindow::DIALOG_WIDTH;
+            banner.layout.height = params.inv_scale_v(banner_height);
+            content_top = banner.layout.bottom();
+        }
+
         //Update the description label layout
         self.descr_label.x = 5;
-        self.descr_label.y = 5;
+        self.descr_label.y = content_top + 5;
         self.descr_label.width = DialogWindow::DIALOG_WIDTH - 10;
         self.descr_label.height = params.inv_scale_h(self.measure_text_height(params.scale_h(self.descr_label.width), self.descr_text)?);
 
@@ -485,13 +669,45 @@ impl<'a> DialogWindow<'a> {
         self.progress_bar.width = DialogWindow::DIALOG_WIDTH - 10;
         self.progress_bar.height = (unsafe { GetSystemMetrics(SM_CYVSCROLL) } * DialogWindow::BASE_UNIT_DIVS.1) / self.v_base;
 
+        //Update the cancel button layout (using the standard button size)
+        self.cancel_button.width = 50;
+        self.cancel_button.height = 14;
+        self.cancel_button.x = DialogWindow::DIALOG_WIDTH - 5 - self.cancel_button.width;
+        self.cancel_button.y = self.progress_bar.bottom() + 7;
+
         //Give labels breathing room below
         self.descr_label.set_bottom(self.descr_label.bottom() + 2);
         self.progress_label.set_bottom(self.progress_label.bottom() + 2);
 
         //Update the window layout
         self.window_layout.width = DialogWindow::DIALOG_WIDTH;
-        self.window_layout.height = self.progress_bar.bottom() + 5;
+        self.window_layout.height = self.cancel_button.bottom() + 5;
+
+        Ok(())
+    }
+
+    //Banners are shown at their actual size (scaled by the DPI), but are shrunk to fit the dialog's width
+    fn banner_pixel_size(banner: &BannerImage, params: &LayoutParams) -> (i32, i32) {
+        let max_width = params.scale_h(DialogWindow::DIALOG_WIDTH);
+        let (width, height) = (banner.size.0 * params.dpi.0 / DPI_100P, banner.size.1 * params.dpi.1 / DPI_100P);
+        if width > max_width {
+            (max_width, (height * max_width / width).max(1))
+        } else {
+            (width, height)
+        }
+    }
+
+    fn update_banner_icon(&mut self, dpi: (i32, i32)) -> Result<(), WinError> {
+        let params = self.calc_layout_params(dpi);
+        let Some(banner) = &mut self.banner else { return Ok(()); };
+
+        //Recreate the banner icon at the new size, then swap it into the control
+        //The old icon is only destroyed once the control no longer uses it
+        let icon = IconHolder::create_from_png(banner.data, DialogWindow::banner_pixel_size(banner, &params))?;
+        unsafe {
+            SendMessageA(banner.layout.handle, STM_SETIMAGE, WPARAM(IMAGE_ICON.0 as usize), LPARAM(icon.0.0));
+        }
+        banner.icon = icon;
 
         Ok(())
     }
@@ -578,6 +794,64 @@ impl Drop for FontHolder {
     }
 }
 
+#[derive(Default)]
+struct IconHolder(HICON);
+
+impl IconHolder {
+    fn create_from_png(data: &[u8], size: (i32, i32)) -> Result<IconHolder, WinError> {
+        //Icon resources may consist of plain PNG data since Windows Vista
+        unsafe { CreateIconFromResourceEx(data, true, 0x00030000, size.0, size.1, LR_DEFAULTCOLOR).map(IconHolder) }
+    }
+}
+
+impl Drop for IconHolder {
+    fn drop(&mut self) {
+        if !self.0.is_invalid() {
+            if let Err(err) = unsafe { DestroyIcon(self.0) } {
+                panic!("failed to free HICON handle: {err:?}");
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct BrushHolder(HBRUSH);
+
+impl BrushHolder {
+    fn create_solid(color: COLORREF) -> Result<BrushHolder, WinError> {
+        unsafe {
+            let brush = CreateSolidBrush(color);
+            if !brush.is_invalid() {
+                Ok(BrushHolder(brush))
+            } else {
+                Err(WinError::from_win32())
+            }
+        }
+    }
+
+    fn has_brush(&self) -> bool { !self.0.is_invalid() }
+}
+
+impl Drop for BrushHolder {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.0.is_invalid() && !DeleteObject(self.0).as_bool() {
+                panic!("failed to free HBRUSH handle: {:?}", WinError::from_win32());
+            }
+        }
+    }
+}
+
+//Reads the image dimensions from the PNG header
+fn png_dimensions(data: &[u8]) -> Option<(i32, i32)> {
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if data.len() < 24 || !data.starts_with(PNG_SIGNATURE) || &data[12..16] != b"IHDR" { return None; }
+
+    let width = i32::try_from(u32::from_be_bytes(data[16..20].try_into().unwrap())).ok()?;
+    let height = i32::try_from(u32::from_be_bytes(data[20..24].try_into().unwrap())).ok()?;
+    (width > 0 && height > 0).then_some((width, height))
+}
+
 struct DCDropper(HWND, HDC);
 impl Drop for DCDropper {
     fn drop(&mut self) {
@@ -0,0 +1,152 @@
+use std::{fs, io::{self, Write}, iter, path::{Path, PathBuf}, sync::{Mutex, OnceLock}, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::cfg;
+
+use super::{LogHook, LogLevel, LogSinkKind};
+
+struct LogFile {
+    path: PathBuf,
+    file: fs::File,
+    size: u64
+}
+
+static LOG_FILE: Mutex<Option<LogFile>> = Mutex::new(None);
+static LOG_FILE_SINK: OnceLock<LogHook> = OnceLock::new();
+
+//Opens the log file in the first writable log directory, rotating old log files if required
+pub fn open_log_file(install_dir: &Path) {
+    if !cfg::USE_LOG_FILE { return; }
+
+    let log_dirs = cfg::LOG_DIR_PATHS.iter().map(|dir| install_dir.join(dir)).chain(state_dir());
+    for log_dir in log_dirs {
+        let log_path = log_dir.join(cfg::LOG_FILE_NAME);
+        match LogFile::open(&log_path) {
+            Ok(log_file) => {
+                *LOG_FILE.lock().unwrap() = Some(log_file);
+                LOG_FILE_SINK.get_or_init(|| LogHook::register(LogSinkKind::Passive, write_to_log_file));
+                crate::log!("Piton apphost v{} started (PID {}), logging to '{}'", env!("CARGO_PKG_VERSION"), std::process::id(), log_path.display());
+                return;
+            }
+            Err(err) => crate::log_warn!("Failed to open log file '{}': {err}", log_path.display())
+        }
+    }
+}
+
+pub fn log_file_path() -> Option<PathBuf> { LOG_FILE.lock().unwrap().as_ref().map(|f| f.path.clone()) }
+
+//Returns all existing log files (including rotated ones) in any of the log directories
+pub fn find_log_files(install_dir: &Path) -> Vec<PathBuf> {
+    let log_dirs = cfg::LOG_DIR_PATHS.iter().map(|dir| install_dir.join(dir)).chain(state_dir());
+    log_dirs.flat_map(|log_dir| {
+        let log_path = log_dir.join(cfg::LOG_FILE_NAME);
+        let rotated_paths = (1..=cfg::LOG_FILE_MAX_COUNT).map(|idx| rotated_log_path(&log_path, idx)).collect::<Vec<_>>();
+        iter::once(log_path).chain(rotated_paths)
+    }).filter(|path| path.is_file()).collect()
+}
+
+fn write_to_log_file(level: LogLevel, msg: &str) {
+    let mut log_file = LOG_FILE.lock().unwrap();
+    let Some(file) = log_file.as_mut() else { return; };
+
+    //Logging must never bring down the apphost, so close the log file if writing fails
+    let line = format!("{} {level:<5} [PITON] {msg}\n", fmt_timestamp(SystemTime::now()));
+    if file.write_line(&line).is_err() {
+        *log_file = None;
+    }
+}
+
+impl LogFile {
+    fn open(path: &Path) -> io::Result<LogFile> {
+        fs::create_dir_all(path.parent().unwrap())?;
+
+        //Rotate the log file if it has grown too large
+        if fs::metadata(path).is_ok_and(|m| m.len() >= cfg::LOG_FILE_MAX_SIZE) {
+            rotate_log_files(path)?;
+        }
+
+        let file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(LogFile { path: PathBuf::from(path), file, size })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        //Rotate the log file if writing this line would exceed the size limit
+        if self.size > 0 && self.size + line.len() as u64 > cfg::LOG_FILE_MAX_SIZE {
+            rotate_log_files(&self.path)?;
+            self.file = fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+            self.size = 0;
+        }
+
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+fn rotated_log_path(path: &Path, idx: usize) -> PathBuf {
+    //piton.log -> piton.<idx>.log
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!("{stem}.{idx}.{}", ext.to_string_lossy())),
+        None => path.with_file_name(format!("{stem}.{idx}"))
+    }
+}
+
+fn rotate_log_files(path: &Path) -> io::Result<()> {
+    //Shift all old log files up by one, dropping the oldest one
+    if cfg::LOG_FILE_MAX_COUNT == 0 {
+        return fs::remove_file(path);
+    }
+
+    let oldest_path = rotated_log_path(path, cfg::LOG_FILE_MAX_COUNT);
+    if oldest_path.exists() {
+        fs::remove_file(oldest_path)?;
+    }
+
+    for idx in (1..cfg::LOG_FILE_MAX_COUNT).rev() {
+        let old_path = rotated_log_path(path, idx);
+        if old_path.exists() {
+            fs::rename(old_path, rotated_log_path(path, idx + 1))?;
+        }
+    }
+
+    fs::rename(path, rotated_log_path(path, 1))
+}
+
+//Determines the per-user state directory for the current apphost, which is used when the install directory isn't writable
+fn state_dir() -> Option<PathBuf> {
+    let exe_path = std::env::current_exe().ok()?;
+    let app_id = exe_path.file_stem()?.to_string_lossy().into_owned();
+
+    #[cfg(target_os = "windows")]
+    let base_dir = std::env::var_os("LOCALAPPDATA").map(PathBuf::from).map(|d| d.join("Piton"));
+
+    #[cfg(target_os = "macos")]
+    let base_dir = std::env::var_os("HOME").map(PathBuf::from).map(|d| d.join("Library/Logs/Piton"));
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base_dir = std::env::var_os("XDG_STATE_HOME").map(PathBuf::from)
+        .filter(|d| d.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|d| PathBuf::from(d).join(".local/state")))
+        .map(|d| d.join("piton"));
+
+    Some(base_dir?.join(app_id))
+}
+
+fn fmt_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, day_secs) = (secs / 86400, secs % 86400);
+
+    //Convert the day count to a civil date (see https://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}Z", day_secs / 3600, (day_secs / 60) % 60, day_secs % 60)
+}
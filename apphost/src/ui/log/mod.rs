@@ -0,0 +1,196 @@
+use std::{sync::{Arc, RwLock, atomic::{AtomicU8, AtomicU64, Ordering}}, fmt::{Display, Formatter, self}, str::FromStr};
+
+use crate::cfg;
+
+mod file;
+pub use file::{open_log_file, log_file_path, find_log_files};
+
+#[macro_export]
+macro_rules! log {
+    ($($msg_arg:tt)+) => {
+        $crate::ui::log::write_log($crate::ui::log::LogLevel::Info, &format!($($msg_arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! log_error {
+    ($($msg_arg:tt)+) => {
+        $crate::ui::log::write_log($crate::ui::log::LogLevel::Error, &format!($($msg_arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ($($msg_arg:tt)+) => {
+        $crate::ui::log::write_log($crate::ui::log::LogLevel::Warn, &format!($($msg_arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ($($msg_arg:tt)+) => {
+        $crate::ui::log::write_log($crate::ui::log::LogLevel::Debug, &format!($($msg_arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! log_trace {
+    ($($msg_arg:tt)+) => {
+        $crate::ui::log::write_log($crate::ui::log::LogLevel::Trace, &format!($($msg_arg)+))
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5
+}
+
+impl LogLevel {
+    const fn from_u8(val: u8) -> Option<LogLevel> {
+        match val {
+            1 => Some(LogLevel::Error),
+            2 => Some(LogLevel::Warn),
+            3 => Some(LogLevel::Info),
+            4 => Some(LogLevel::Debug),
+            5 => Some(LogLevel::Trace),
+            _ => None
+        }
+    }
+
+    const fn to_u8(level: Option<LogLevel>) -> u8 {
+        match level {
+            Some(level) => level as u8,
+            None => 0
+        }
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Trace => "TRACE"
+        })
+    }
+}
+
+//Parses a verbosity setting - 'off' / 'none' disables logging, and is parsed as None
+pub struct LogVerbosity(pub Option<LogLevel>);
+
+impl FromStr for LogVerbosity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" | "none" | "quiet" => Ok(LogVerbosity(None)),
+            "error" => Ok(LogVerbosity(Some(LogLevel::Error))),
+            "warn" | "warning" => Ok(LogVerbosity(Some(LogLevel::Warn))),
+            "info" => Ok(LogVerbosity(Some(LogLevel::Info))),
+            "debug" => Ok(LogVerbosity(Some(LogLevel::Debug))),
+            "trace" => Ok(LogVerbosity(Some(LogLevel::Trace))),
+            _ => Err(format!("unknown log level '{s}'"))
+        }
+    }
+}
+
+static CONSOLE_LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::to_u8(cfg::CONSOLE_LOG_LEVEL));
+static FILE_LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::to_u8(cfg::LOG_FILE_LEVEL));
+
+//Applies the verbosity override from the PITON_LOG environment variable
+//This happens before any log sinks have been set up, so invalid values are returned for the caller to report later
+pub fn init_log_levels() -> Result<(), String> {
+    let Ok(env_level) = std::env::var("PITON_LOG") else { return Ok(()); };
+    let LogVerbosity(level) = env_level.parse::<LogVerbosity>()?;
+    set_log_level(level);
+    Ok(())
+}
+
+//Sets the console verbosity - the log file verbosity is raised to match if required
+pub fn set_log_level(level: Option<LogLevel>) {
+    CONSOLE_LOG_LEVEL.store(LogLevel::to_u8(level), Ordering::Relaxed);
+    FILE_LOG_LEVEL.fetch_max(LogLevel::to_u8(level), Ordering::Relaxed);
+}
+
+pub fn write_log(level: LogLevel, msg: &str) {
+    let passes = |lvl: &AtomicU8| LogLevel::from_u8(lvl.load(Ordering::Relaxed)).is_some_and(|l| level <= l);
+    let (log_console, log_passive) = (passes(&CONSOLE_LOG_LEVEL), passes(&FILE_LOG_LEVEL));
+    if !log_console && !log_passive { return; }
+
+    //Collect the sinks to invoke, so that they are called without holding the lock
+    //Only the most recently registered console sink receives messages, as it has taken over the console
+    let (console_sink, passive_sinks) = {
+        let sinks = LOG_SINKS.read().unwrap();
+        let console_sink = sinks.iter().rev().find(|s| s.kind == LogSinkKind::Console).map(|s| s.sink.clone());
+        let passive_sinks = sinks.iter().filter(|s| s.kind == LogSinkKind::Passive).map(|s| s.sink.clone()).collect::<Vec<_>>();
+        (console_sink, passive_sinks)
+    };
+
+    if log_passive {
+        for sink in passive_sinks {
+            sink(level, msg);
+        }
+    }
+
+    if log_console {
+        if let Some(sink) = console_sink {
+            sink(level, msg);
+        } else if level <= LogLevel::Warn {
+            eprintln!("{}", fmt_console_msg(level, msg));
+        } else {
+            println!("{}", fmt_console_msg(level, msg));
+        }
+    }
+}
+
+pub fn fmt_console_msg(level: LogLevel, msg: &str) -> String {
+    match level {
+        LogLevel::Info => format!("[PITON] {msg}"),
+        _ => format!("[PITON] [{level}] {msg}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogSinkKind {
+    //Console sinks take over console output while they are registered, and are subject to the console verbosity
+    Console,
+    //Passive sinks receive all messages subject to the log file verbosity
+    Passive
+}
+
+pub type LogSinkFunc = dyn Fn(LogLevel, &str) + Send + Sync;
+
+struct LogSink {
+    id: u64,
+    kind: LogSinkKind,
+    sink: Arc<LogSinkFunc>
+}
+
+static LOG_SINKS: RwLock<Vec<LogSink>> = RwLock::new(Vec::new());
+static NEXT_SINK_ID: AtomicU64 = AtomicU64::new(0);
+
+//Registers a log sink for as long as the returned hook is alive
+#[must_use = "the log sink is removed again when the hook is dropped"]
+pub struct LogHook(u64);
+
+impl LogHook {
+    pub fn register(kind: LogSinkKind, sink: impl Fn(LogLevel, &str) + Send + Sync + 'static) -> LogHook {
+        let id = NEXT_SINK_ID.fetch_add(1, Ordering::Relaxed);
+        LOG_SINKS.write().unwrap().push(LogSink { id, kind, sink: Arc::new(sink) });
+        LogHook(id)
+    }
+}
+
+impl Drop for LogHook {
+    fn drop(&mut self) {
+        let mut sinks = LOG_SINKS.write().unwrap();
+        let idx = sinks.iter().position(|s| s.id == self.0).expect("log sink has already been removed");
+        sinks.remove(idx);
+    }
+}
@@ -1,12 +1,18 @@
 use std::error::Error;
 
-use crate::cfg::UI_DRIVER;
+use crate::{cfg::UI_DRIVER, log_debug};
 
 #[cfg(feature = "ui-cli")] mod cli;
 #[cfg(feature = "ui-gui")] mod gui;
 
 pub mod log;
 
+pub mod progress;
+pub use progress::*;
+
+pub mod report;
+pub use report::*;
+
 #[derive(serde::Deserialize)]
 pub enum UIDriver {
     #[serde(rename = "none")]
@@ -22,16 +28,43 @@ pub enum UIDriver {
 }
 
 pub trait ProgressAction {
+    fn update_progress(&self, update: &ProgressUpdate) {
+        if update.indeterminate {
+            self.set_indeterminate_progress(&update.to_string());
+        } else {
+            self.set_progress(&update.to_string(), update.fract);
+        }
+    }
     fn set_progress(&self, txt: &str, fract: f64);
+
+    //Shows that the action is still working without knowing how far along it is (e.g. while connecting or counting the work to be done)
+    //Any following set_progress call switches back to regular progress
+    fn set_indeterminate_progress(&self, txt: &str);
+
     fn is_cancelled(&self) -> bool;
 }
 
+//Loads the branding shown by the GUI driver from the given install directory
+#[cfg(feature = "ui-gui")]
+pub fn init_branding(install_dir: &std::path::Path) { gui::branding::init(install_dir); }
+
+//Falls back to the CLI driver if the GUI driver isn't supported on this system (e.g. because GTK isn't installed)
+fn ui_driver() -> &'static UIDriver {
+    #[cfg(feature = "ui-gui")]
+    if matches!(UI_DRIVER, UIDriver::Gui) && !gui::is_supported() {
+        log_debug!("GUI driver isn't supported, falling back to the CLI driver");
+        return &UIDriver::Cli;
+    }
+    &UI_DRIVER
+}
+
 pub fn run_progress_action<T: Send>(descr: &str, action: impl FnOnce(&dyn ProgressAction) -> T + Send) -> Result<Option<T>, Box<dyn Error>> {
-    match UI_DRIVER {
+    match ui_driver() {
         UIDriver::None => {
             struct NoOpProgressAction;
             impl ProgressAction for NoOpProgressAction {
                 fn set_progress(&self, _txt: &str, _fract: f64) {}
+                fn set_indeterminate_progress(&self, _txt: &str) {}
                 fn is_cancelled(&self) -> bool { false }
             }
 
@@ -46,11 +79,37 @@ pub fn run_progress_action<T: Send>(descr: &str, action: impl FnOnce(&dyn Progre
     }
 }
 
-pub fn show_error_msg(msg: &str) {
-    match UI_DRIVER {
+pub fn show_error(report: &ErrorReport) {
+    match ui_driver() {
         #[cfg(feature = "ui-gui")]
-        UIDriver::Gui => gui::show_error_msgbox(msg).expect("failed to show the error message box"),
+        UIDriver::Gui => { gui::show_error_msgbox(report, false).expect("failed to show the error message box"); },
 
-        _ => eprintln!("{msg}")
+        _ => eprintln!("{report}")
     };
+}
+
+//Sends a desktop notification, which is currently only supported by the GTK driver
+pub fn notify(summary: &str, body: &str) {
+    match ui_driver() {
+        #[cfg(all(feature = "ui-gui", target_os = "linux"))]
+        UIDriver::Gui => gui::send_notification(summary, body),
+
+        _ => log_debug!("Not sending desktop notification '{summary}': {body}")
+    }
+}
+
+//Shows an error which the user can choose to retry the failed operation for - returns true if they did
+pub fn show_retryable_error(report: &ErrorReport) -> bool {
+    match ui_driver() {
+        #[cfg(feature = "ui-gui")]
+        UIDriver::Gui => gui::show_error_msgbox(report, true).expect("failed to show the error message box"),
+
+        #[cfg(feature = "ui-cli")]
+        UIDriver::Cli => cli::prompt_retry(report),
+
+        _ => {
+            eprintln!("{report}");
+            false
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,110 @@
+use std::{fmt::{Display, Formatter, self}, time::Duration};
+
+use bytesize::ByteSize;
+
+use crate::tr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressStage {
+    Connecting,
+    Downloading,
+    Verifying,
+    Extracting,
+    Finalizing
+}
+
+impl Display for ProgressStage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&match self {
+            ProgressStage::Connecting => tr!("progress.connecting"),
+            ProgressStage::Downloading => tr!("progress.downloading"),
+            ProgressStage::Verifying => tr!("progress.verifying"),
+            ProgressStage::Extracting => tr!("progress.extracting"),
+            ProgressStage::Finalizing => tr!("progress.finalizing")
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgressUpdate {
+    pub stage: ProgressStage,
+    pub fract: f64,
+    pub indeterminate: bool,
+
+    pub bytes_done: Option<u64>,
+    pub bytes_total: Option<u64>,
+    pub throughput: Option<u64>, //In bytes per second
+    pub eta: Option<Duration>,
+
+    pub entries_done: Option<usize>,
+    pub entries_total: Option<usize>
+}
+
+impl ProgressUpdate {
+    pub const fn new(stage: ProgressStage, fract: f64) -> ProgressUpdate {
+        ProgressUpdate {
+            stage, fract, indeterminate: false,
+            bytes_done: None, bytes_total: None, throughput: None, eta: None,
+            entries_done: None, entries_total: None
+        }
+    }
+
+    //Used for stages where it isn't known how much work is left
+    pub const fn indeterminate(stage: ProgressStage) -> ProgressUpdate {
+        let mut update = ProgressUpdate::new(stage, 0_f64);
+        update.indeterminate = true;
+        update
+    }
+
+    pub const fn with_bytes(mut self, done: u64, total: u64) -> ProgressUpdate {
+        self.bytes_done = Some(done);
+        self.bytes_total = Some(total);
+        self
+    }
+
+    pub const fn with_throughput(mut self, throughput: u64, eta: Option<Duration>) -> ProgressUpdate {
+        self.throughput = Some(throughput);
+        self.eta = eta;
+        self
+    }
+
+    pub const fn with_entries(mut self, done: usize, total: usize) -> ProgressUpdate {
+        self.entries_done = Some(done);
+        self.entries_total = Some(total);
+        self
+    }
+}
+
+impl Display for ProgressUpdate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let (Some(done), Some(total)) = (self.bytes_done, self.bytes_total) {
+            f.write_str(&tr!("progress.count", stage = self.stage, done = ByteSize::b(done), total = ByteSize::b(total)))?;
+
+            let throughput = self.throughput.map(|t| format!("{}/s", ByteSize::b(t)));
+            match (throughput, self.eta) {
+                (Some(throughput), Some(eta)) => write!(f, " {}", tr!("progress.throughput-eta", throughput = throughput, eta = FmtDuration(eta)))?,
+                (Some(throughput), None) => write!(f, " {}", tr!("progress.throughput", throughput = throughput))?,
+                _ => {}
+            }
+
+            Ok(())
+        } else if let (Some(done), Some(total)) = (self.entries_done, self.entries_total) {
+            f.write_str(&tr!("progress.count", stage = self.stage, done = done, total = total))
+        } else {
+            write!(f, "{}", self.stage)
+        }
+    }
+}
+
+pub struct FmtDuration(pub Duration);
+
+impl Display for FmtDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+        f.write_str(&match secs {
+            0..=59 => tr!("duration.seconds", s = secs),
+            60..=3599 => tr!("duration.minutes", m = secs / 60, s = format!("{:02}", secs % 60)),
+            _ => tr!("duration.hours", h = secs / 3600, m = format!("{:02}", (secs / 60) % 60))
+        })
+    }
+}
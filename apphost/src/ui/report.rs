@@ -0,0 +1,64 @@
+use std::{error::Error, fmt::{Display, Formatter, self, Write}, path::PathBuf, sync::Mutex};
+
+use crate::{i18n, tr};
+
+use super::log::log_file_path;
+
+//Additional information included in the details of all error reports (e.g. the target ID), keyed by their message catalog label
+static REPORT_CONTEXT: Mutex<Vec<(&'static str, String)>> = Mutex::new(Vec::new());
+
+pub fn set_report_context(label_key: &'static str, value: impl Display) {
+    let mut ctx = REPORT_CONTEXT.lock().unwrap();
+    let value = value.to_string();
+    match ctx.iter_mut().find(|(key, _)| *key == label_key) {
+        Some(entry) => entry.1 = value,
+        None => ctx.push((label_key, value))
+    }
+}
+
+pub struct ErrorReport {
+    pub summary: String,
+    pub details: String,
+    #[allow(unused)]
+    pub log_path: Option<PathBuf>
+}
+
+impl ErrorReport {
+    pub fn new(summary: String, err: Option<&dyn Error>) -> ErrorReport {
+        let mut details = String::new();
+
+        //Add the error chain
+        if let Some(err) = err {
+            writeln!(details, "{err}").unwrap();
+
+            let mut src = err.source();
+            while let Some(err) = src {
+                writeln!(details, "{}: {err}", tr!("report.caused-by")).unwrap();
+                src = err.source();
+            }
+            details.push('\n');
+        }
+
+        //Add context information
+        for (label_key, value) in REPORT_CONTEXT.lock().unwrap().iter() {
+            writeln!(details, "{}: {value}", i18n::message(label_key)).unwrap();
+        }
+
+        let log_path = log_file_path();
+        if let Some(log_path) = &log_path {
+            writeln!(details, "{}: {}", tr!("report.log-file"), log_path.display()).unwrap();
+        }
+
+        ErrorReport { summary, details: String::from(details.trim_end()), log_path }
+    }
+}
+
+impl Display for ErrorReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.summary)?;
+        if !self.details.is_empty() {
+            write!(f, "\n\n{}", self.details)?;
+        }
+        Ok(())
+    }
+}